@@ -0,0 +1,11 @@
+use std::process::Command;
+
+// The compiler that builds the binary, for the environment printed at the top of a run (see environment.rs):
+// the rustc on PATH when it runs can be another one
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc).arg("--version").output()
+        .map_or("unknown".to_string(), |out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+    println!("cargo:rustc-env=RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-changed=build.rs");
+}
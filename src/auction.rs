@@ -0,0 +1,175 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Barrier, Mutex, RwLock};
+use std::thread;
+use crate::matching::{extend, hopcroft_karp, Assignment, Graph};
+
+const EPS_FACTOR: i64 = 6; // how much epsilon shrinks between scaling phases
+
+// Parallel exact solver: Jacobi auction with epsilon scaling. In a round every unassigned row bids
+// for its best column at once, rows split over the threads; the highest bid on a column wins it
+// (ties to the lower row) and the row that had it bids again in the next round. Costs are scaled
+// by n + 1, so ending with epsilon 1 gives an optimal plan.
+// Auction needs a perfect matching: anything else is solved on the square extension.
+pub fn auction(graph: &Graph, threads: usize) -> Assignment {
+    let square = graph.rows == graph.cols && hopcroft_karp(graph).0 == graph.rows;
+    if square {
+        let row_to_col = auction_perfect(graph, threads);
+        return Assignment::new(row_to_col.into_iter().map(Some).collect(), graph.cols, graph);
+    }
+    let max_cost = graph.adj.iter().flatten().map(|(_, c)| *c).max().unwrap_or(0);
+    let big = max_cost * graph.rows.min(graph.cols) as u32 + 1;
+    let ext = extend(graph, big);
+    let row_to_col = auction_perfect(&ext, threads);
+    let real: Vec<Option<usize>> = row_to_col.iter().take(graph.rows).map(|d| (*d < graph.cols).then_some(*d)).collect();
+    Assignment::new(real, graph.cols, graph)
+}
+
+// (row, column, bid)
+type Bid = (usize, usize, i64);
+
+fn auction_perfect(graph: &Graph, threads: usize) -> Vec<usize> {
+    let n = graph.rows;
+    let threads = threads.max(1);
+    let scale = n as i64 + 1;
+    let max_cost = graph.adj.iter().flatten().map(|(_, c)| *c as i64).max().unwrap_or(0) * scale;
+    let mut row_to_col: Vec<Option<usize>> = vec![None; n];
+    if n == 0 {
+        return vec![];
+    }
+    let prices: RwLock<Vec<i64>> = RwLock::new(vec![0; n]);
+    let todo: RwLock<Vec<usize>> = RwLock::new(vec![]);
+    let eps = AtomicI64::new((max_cost / 2).max(1));
+    let bids: Vec<Mutex<Vec<Bid>>> = (0..threads).map(|_| Mutex::new(vec![])).collect();
+    let start = Barrier::new(threads);
+    let done = Barrier::new(threads);
+    let stop = AtomicBool::new(false);
+    // bids of the w-th share of the unassigned rows
+    let bid = |w: usize| {
+        let todo = todo.read().unwrap();
+        let prices = prices.read().unwrap();
+        let eps = eps.load(Ordering::Relaxed);
+        let mut out = bids[w].lock().unwrap();
+        out.clear();
+        for s in &todo[w * todo.len() / threads..(w + 1) * todo.len() / threads] {
+            // value of a column is -cost - price, the best and the second best
+            let mut best: Option<(i64, usize)> = None;
+            let mut second: Option<i64> = None;
+            for (d, c) in &graph.adj[*s] {
+                let value = -(*c as i64) * scale - prices[*d];
+                match best {
+                    Some((b, _)) if value <= b => second = Some(second.map_or(value, |x| x.max(value))),
+                    _ => {
+                        second = best.map(|(b, _)| b);
+                        best = Some((value, *d));
+                    }
+                }
+            }
+            let (value, d) = best.unwrap();
+            // a single column: any raise keeps it the best, the range of costs is plenty
+            let second = second.unwrap_or(value - max_cost - eps);
+            out.push((*s, d, prices[d] + value - second + eps));
+        }
+    };
+
+    thread::scope(|scope| {
+        for w in 1..threads {
+            let (bid, start, done, stop) = (&bid, &start, &done, &stop);
+            scope.spawn(move || loop {
+                start.wait();
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                bid(w);
+                done.wait();
+            });
+        }
+        let mut col_to_row: Vec<Option<usize>> = vec![None; n];
+        let mut best_bid: Vec<Option<(i64, usize)>> = vec![None; n];
+        loop {
+            // a phase: everything unassigned, prices kept from the last one
+            row_to_col.iter_mut().for_each(|d| *d = None);
+            col_to_row.iter_mut().for_each(|s| *s = None);
+            *todo.write().unwrap() = (0..n).collect();
+            while !todo.read().unwrap().is_empty() {
+                start.wait();
+                bid(0);
+                done.wait();
+                let mut touched: Vec<usize> = vec![];
+                for out in &bids {
+                    for (s, d, b) in out.lock().unwrap().iter() {
+                        match best_bid[*d] {
+                            None => {
+                                best_bid[*d] = Some((*b, *s));
+                                touched.push(*d);
+                            }
+                            Some((bb, bs)) if *b > bb || (*b == bb && *s < bs) => best_bid[*d] = Some((*b, *s)),
+                            _ => {}
+                        }
+                    }
+                }
+                let mut prices = prices.write().unwrap();
+                for d in touched {
+                    let (b, s) = best_bid[d].take().unwrap();
+                    if let Some(prev) = col_to_row[d] {
+                        row_to_col[prev] = None;
+                    }
+                    col_to_row[d] = Some(s);
+                    row_to_col[s] = Some(d);
+                    prices[d] = b;
+                }
+                *todo.write().unwrap() = (0..n).filter(|s| row_to_col[*s].is_none()).collect();
+            }
+            let e = eps.load(Ordering::Relaxed);
+            if e == 1 {
+                break;
+            }
+            eps.store((e / EPS_FACTOR).max(1), Ordering::Relaxed);
+        }
+        stop.store(true, Ordering::Relaxed);
+        start.wait();
+    });
+    row_to_col.into_iter().map(|d| d.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{all_plan_costs, Graph};
+    use crate::{munkres2, test_cost_arr, test_instance};
+    use super::auction;
+
+    #[test]
+    fn auction_matches_munkres2() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(4);
+        for size in [1, 2, 5, 10, 30] {
+            for max in [1, 100, 5000] {
+                let forb = test_instance(&mut cost_arr, &mut rng, size, size, max, 0);
+                let graph = Graph::from_matrix(&cost_arr, &forb, size, size);
+                let expected = munkres2(&cost_arr, &forb, size, size).0 as u64;
+                for threads in [1, 3] {
+                    let plan = auction(&graph, threads);
+                    assert_eq!(plan.size(), size);
+                    assert_eq!(plan.cost, expected, "{}x{}, costs 0..={}, {} thread(s)", size, size, max, threads);
+                }
+            }
+        }
+    }
+
+    // solved on the square extension
+    #[test]
+    fn auction_rectangular_and_sparse() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(5);
+        for (s_size, d_size, forbidden_pct) in [(3, 6, 0), (6, 3, 0), (5, 5, 50), (4, 6, 60), (6, 4, 40)] {
+            for _ in 0..5 {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 50, forbidden_pct);
+                let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                let expected = all_plan_costs(&graph);
+                let plan = auction(&graph, 2);
+                assert_eq!(Some(plan.cost), expected.first().copied(), "{}x{}", s_size, d_size);
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::time::Instant;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::auction::auction;
+use crate::candidates::{candidate_graph, solve_certified, Costs};
+use crate::components::solve_by_components;
+use crate::forbidden::Forbidden;
+use crate::heuristics::lcm_sorted;
+use crate::hybrid::{hybrid, HybridLimit};
+use crate::console;
+use crate::incremental::Incremental;
+use crate::matching::{min_cost_max_matching, min_cost_max_matching_warm, Graph, WarmStart};
+use crate::network_simplex::ns_transport;
+use crate::results;
+use crate::stats::{median, median_ci};
+use crate::transport::{transport, validate, InitialBfs};
+use crate::{average, instance_class, lcm, new_instance, print_summary, random_cost, run_timed, Samples, Solvers, ADAPTIVE_REPEATS, COMPARED, COSTS,
+    DSIZE, FORBIDDEN_PCT, MAX_ITER, MAX_VALUE, MIN_VALUE, RESULTS_FILE, SLOW_REPEATS, SLOW_SOLVERS, SSIZE, TIME_BUDGET};
+
+const TICKS: usize = 20;
+const CHANGED: usize = 5; // how many cabs and orders change their costs in a tick
+const BATCHES: usize = 10;
+const PERTURBED_PCT: u32 = 5; // how many percent of cells get a new cost between batches
+const LCM_SIZES: [(usize, usize); 4] = [(250, 250), (500, 500), (1000, 1000), (1000, 2000)];
+const LCM_WIDE_MAX_VALUE: u16 = 1800;
+const HYBRID_LIMITS: [HybridLimit; 6] = [HybridLimit::Threshold(1), HybridLimit::Threshold(3), HybridLimit::Threshold(10),
+    HybridLimit::Budget(1000), HybridLimit::Budget(500), HybridLimit::Budget(100)];
+const CANDIDATES_K: [usize; 4] = [2, 5, 10, 20];
+const AREA: f64 = 10000.0; // cabs and orders are spread over AREA x AREA
+const DISTRICTS: usize = 8; // cabs and orders are only paired within their district
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+const TRANSPORT_SIZES: [(usize, usize); 3] = [(50, 50), (100, 150), (200, 200)];
+const MAX_QUANTITY: u32 = 10;
+const TRANSPORT_CHECK_UNITS: u32 = 2000; // up to how many units the plan is checked against unit assignment
+const TARGET_CI_PCT: f64 = 5.0; // width of the 95% confidence interval of the median, percent of the median
+
+// Dispatcher ticks: a few cabs and orders change their costs, some leave and some come.
+// Incremental re-optimisation against solving the whole instance again.
+pub fn bench_incremental(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) {
+    let mut rng = rand::thread_rng();
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    let start = Instant::now();
+    let mut inc = Incremental::new(&graph, MAX_VALUE as u32, SSIZE);
+    println!("Incremental: initial solve {} ms", start.elapsed().as_millis());
+
+    let mut inc_time: Vec<u128> = vec![];
+    let mut full_time: Vec<u128> = vec![];
+    let mut rows: Vec<usize> = (0..s_size).collect(); // ids still in the game
+    let mut cols: Vec<usize> = (0..d_size).collect();
+    for tick in 0..TICKS {
+        let start = Instant::now();
+        let mut augmentations: usize = 0;
+        for _ in 0..CHANGED {
+            let s = rows[rng.gen_range(0..rows.len())];
+            inc.update_row(s, &random_edges(&mut rng, &cols));
+            let d = cols[rng.gen_range(0..cols.len())];
+            inc.update_col(d, &random_edges(&mut rng, &rows));
+        }
+        // one cab leaves and one comes, the same with orders
+        let idx = rng.gen_range(0..rows.len());
+        inc.remove_row(rows.swap_remove(idx));
+        let idx = rng.gen_range(0..cols.len());
+        inc.remove_col(cols.swap_remove(idx));
+        rows.push(inc.add_row(&random_edges(&mut rng, &cols)));
+        cols.push(inc.add_col(&random_edges(&mut rng, &rows)));
+        augmentations += inc.optimise();
+        inc_time.push(start.elapsed().as_micros());
+        let inc_ret = inc.assignment();
+
+        let start = Instant::now();
+        let full_ret = min_cost_max_matching(&live_graph(&inc.real_graph(), &rows, &cols));
+        full_time.push(start.elapsed().as_micros());
+
+        println!("Tick {}: incremental {} us ({} augmentations), full re-solve {} us",
+            tick, inc_time[tick], augmentations, full_time[tick]);
+        if inc_ret.cost != full_ret.cost || inc_ret.size() != full_ret.size() {
+            println!("Incremental plan is wrong, cost {} size {}, should be {} size {}",
+                inc_ret.cost, inc_ret.size(), full_ret.cost, full_ret.size());
+        }
+    }
+    println!("Incremental: Avg: {} us, full re-solve: Avg: {} us", average(&inc_time), average(&full_time));
+}
+
+// Consecutive dispatch batches differ in a few cells only; the solver starts from the potentials
+// (and the plan) of the previous batch instead of from scratch
+pub fn bench_warm_start(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) {
+    let mut rng = rand::thread_rng();
+    let mut cold_time: Vec<u128> = vec![];
+    let mut pot_time: Vec<u128> = vec![];
+    let mut warm_time: Vec<u128> = vec![];
+    let mut hint = WarmStart::default();
+    for batch in 0..BATCHES {
+        if batch > 0 {
+            for row in cost_arr.iter_mut().take(s_size) {
+                for val in row.iter_mut().take(d_size) {
+                    if rng.gen_ratio(PERTURBED_PCT, 100) {
+                        *val = rng.gen_range(MIN_VALUE..MAX_VALUE);
+                    }
+                }
+            }
+        }
+        let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+
+        let start = Instant::now();
+        let cold = min_cost_max_matching(&graph);
+        let cold_elapsed = start.elapsed().as_micros();
+
+        let start = Instant::now();
+        let pot_hint = WarmStart { row_to_col: None, ..hint.clone() };
+        let (pot, _) = min_cost_max_matching_warm(&graph, &pot_hint);
+        let pot_elapsed = start.elapsed().as_micros();
+
+        let start = Instant::now();
+        let (warm, next) = min_cost_max_matching_warm(&graph, &hint);
+        let warm_elapsed = start.elapsed().as_micros();
+        hint = next;
+
+        if pot.cost != cold.cost || warm.cost != cold.cost || warm.size() != cold.size() {
+            println!("Warm start gave a different plan, cost {} (potentials only {}), should be {}", warm.cost, pot.cost, cold.cost);
+        }
+        if batch == 0 { // nothing to start from yet
+            println!("Batch 0: cold {} us, no hint {} us", cold_elapsed, warm_elapsed);
+            continue;
+        }
+        println!("Batch {}: cold {} us, warm potentials {} us, warm potentials + plan {} us, speed-up {:.1}x",
+            batch, cold_elapsed, pot_elapsed, warm_elapsed, cold_elapsed as f32 / warm_elapsed.max(1) as f32);
+        cold_time.push(cold_elapsed);
+        pot_time.push(pot_elapsed);
+        warm_time.push(warm_elapsed);
+    }
+    println!("Cold: Avg: {} us, warm potentials: Avg: {} us, warm potentials + plan: Avg: {} us",
+        average(&cold_time), average(&pot_time), average(&warm_time));
+}
+
+// The rescanning LCM against the one sorting the cells once, both have to make the same plan.
+// With the usual narrow cost range the rescan mostly stops early at a MIN_VALUE cell, so a wide one is run too.
+pub fn bench_lcm(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &mut Forbidden) {
+    let mut rng = rand::thread_rng();
+    for (s_size, d_size) in LCM_SIZES {
+        random_cost(cost_arr, forb, s_size, d_size);
+        for max_value in [MAX_VALUE, LCM_WIDE_MAX_VALUE] {
+            if max_value != MAX_VALUE {
+                for row in cost_arr.iter_mut().take(s_size) {
+                    for val in row.iter_mut().take(d_size) {
+                        *val = rng.gen_range(MIN_VALUE..max_value);
+                    }
+                }
+            }
+            let start = Instant::now();
+            let (lcm_cost, lcm_plan) = lcm(cost_arr, forb, s_size, d_size);
+            let lcm_time = start.elapsed().as_millis();
+            let start = Instant::now();
+            let (sorted_cost, sorted_plan) = lcm_sorted(cost_arr, forb, s_size, d_size);
+            let sorted_time = start.elapsed().as_millis();
+            println!("LCM {}x{}, costs {}..{}: rescanning {} ms, sorted {} ms, cost {}",
+                s_size, d_size, MIN_VALUE, max_value, lcm_time, sorted_time, lcm_cost);
+            if lcm_cost != sorted_cost || lcm_plan != sorted_plan {
+                println!("LCM plans differ, rescanning {} sorted {}", lcm_cost, sorted_cost);
+            }
+        }
+    }
+}
+
+// How much LCM should fix before the exact solver: time saved against quality lost
+pub fn bench_hybrid(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) {
+    let start = Instant::now();
+    let exact = min_cost_max_matching(&Graph::from_matrix(cost_arr, forb, s_size, d_size));
+    let exact_time = start.elapsed().as_millis();
+    println!("Exact: {} ms, cost {}, {} pairs", exact_time, exact.cost, exact.size());
+    for limit in HYBRID_LIMITS {
+        let start = Instant::now();
+        let (ret, fixed) = hybrid(cost_arr, forb, s_size, d_size, limit, min_cost_max_matching);
+        let elapsed = start.elapsed().as_millis();
+        println!("Hybrid {:?}: {} fixed by LCM, {} ms ({} ms saved), cost {} ({} lost), {} pairs", limit, fixed, elapsed,
+            exact_time as i128 - elapsed as i128, ret.cost, ret.cost as i64 - exact.cost as i64, ret.size());
+    }
+}
+
+// Transportation simplex from both initial solutions; small instances are checked against
+// the unit-capacity solver on every unit of supply and demand as its own row and column
+pub fn bench_transport(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &mut Forbidden) {
+    let mut rng = rand::thread_rng();
+    for (s_size, d_size) in TRANSPORT_SIZES {
+        random_cost(cost_arr, forb, s_size, d_size);
+        let supply: Vec<u32> = (0..s_size).map(|_| rng.gen_range(1..=MAX_QUANTITY)).collect();
+        let demand: Vec<u32> = (0..d_size).map(|_| rng.gen_range(1..=MAX_QUANTITY)).collect();
+        let (total_supply, total_demand) = (supply.iter().sum::<u32>(), demand.iter().sum::<u32>());
+        println!("Transport {}x{}: supply {} demand {}", s_size, d_size, total_supply, total_demand);
+        let mut costs: Vec<u64> = vec![];
+        for init in [InitialBfs::Lcm, InitialBfs::Vogel] {
+            let start = Instant::now();
+            let plan = transport(cost_arr, forb, &supply, &demand, init);
+            let elapsed = start.elapsed().as_millis();
+            println!("  {:?} start: cost {}, {} pivots, {} ms, final cost {}", init, plan.initial_cost, plan.iterations, elapsed, plan.cost);
+            if validate(&plan, forb, &supply, &demand) {
+                costs.push(plan.cost);
+            }
+        }
+        if costs.len() == 2 && costs[0] != costs[1] {
+            println!("  Transport costs differ between starts: {} {}", costs[0], costs[1]);
+        }
+        let start = Instant::now();
+        let (_, ns_cost) = ns_transport(cost_arr, forb, &supply, &demand);
+        println!("  Network simplex: {} ms, cost {}", start.elapsed().as_millis(), ns_cost);
+        if !costs.is_empty() && ns_cost != costs[0] {
+            println!("  Transport cost {} but network simplex {}", costs[0], ns_cost);
+        }
+        if !costs.is_empty() && total_supply.max(total_demand) <= TRANSPORT_CHECK_UNITS {
+            let units = unit_graph(cost_arr, forb, &supply, &demand);
+            let exact = min_cost_max_matching(&units);
+            if exact.cost != costs[0] {
+                println!("  Transport cost {} but unit assignment {}", costs[0], exact.cost);
+            }
+        }
+    }
+}
+
+// every unit of supply (demand) as a row (column) of its own
+fn unit_graph(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, supply: &[u32], demand: &[u32]) -> Graph {
+    let cols: Vec<usize> = demand.iter().enumerate().flat_map(|(d, q)| std::iter::repeat_n(d, *q as usize)).collect();
+    let mut adj: Vec<Vec<(usize, u32)>> = vec![];
+    for (s, q) in supply.iter().enumerate() {
+        let edges: Vec<(usize, u32)> = cols.iter().enumerate().filter(|(_, d)| !forb.is_forbidden(s, **d))
+            .map(|(i, d)| (i, cost_arr[s][*d] as u32)).collect();
+        for _ in 0..*q {
+            adj.push(edges.clone());
+        }
+    }
+    Graph { rows: adj.len(), cols: cols.len(), adj }
+}
+
+// k-nearest candidate graphs against the full instance, on the cost matrix and on random coordinates
+pub fn bench_candidates(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) {
+    let mut rng = rand::thread_rng();
+    let cabs: Vec<(f64, f64)> = (0..s_size).map(|_| (rng.gen_range(0.0..AREA), rng.gen_range(0.0..AREA))).collect();
+    let orders: Vec<(f64, f64)> = (0..d_size).map(|_| (rng.gen_range(0.0..AREA), rng.gen_range(0.0..AREA))).collect();
+    for (name, costs) in [("Matrix", Costs::Matrix { cost_arr, forb, s_size, d_size }), ("Points", Costs::Points { cabs: &cabs, orders: &orders })] {
+        let full = costs.full_graph();
+        let start = Instant::now();
+        let exact = min_cost_max_matching(&full);
+        println!("{} {}x{}: full graph {} ms, cost {}, {} edges", name, s_size, d_size, start.elapsed().as_millis(), exact.cost,
+            full.adj.iter().map(|e| e.len()).sum::<usize>());
+        for k in CANDIDATES_K {
+            let start = Instant::now();
+            let graph = candidate_graph(&costs, k);
+            let edges: usize = graph.adj.iter().map(|e| e.len()).sum();
+            let build_time = start.elapsed().as_millis();
+            let ret = solve_certified(&costs, graph);
+            println!("  k={}: {} edges, built in {} ms, certified in {} ms, {} rounds, {} edges added, cost {}",
+                k, edges, build_time, start.elapsed().as_millis() - build_time, ret.rounds, ret.added, ret.plan.cost);
+            if ret.plan.cost != exact.cost || ret.plan.size() != exact.size() {
+                println!("  Candidate plan differs: cost {} size {}, full {} size {}", ret.plan.cost, ret.plan.size(), exact.cost, exact.size());
+            }
+        }
+    }
+}
+
+// A city split into districts, no pair across them: one solve of everything against one per component
+pub fn bench_components(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &mut Forbidden, s_size: usize, d_size: usize) {
+    let mut rng = rand::thread_rng();
+    let cab_district: Vec<usize> = (0..s_size).map(|_| rng.gen_range(0..DISTRICTS)).collect();
+    let order_district: Vec<usize> = (0..d_size).map(|_| rng.gen_range(0..DISTRICTS)).collect();
+    for (s, cab) in cab_district.iter().enumerate() {
+        for (d, order) in order_district.iter().enumerate() {
+            if cab != order {
+                forb.forbid(s, d);
+            }
+        }
+    }
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    let start = Instant::now();
+    let whole = min_cost_max_matching(&graph);
+    println!("Whole graph: {} ms, cost {}, {} pairs", start.elapsed().as_millis(), whole.cost, whole.size());
+    let mut threads: Vec<usize> = vec![1, 2, std::thread::available_parallelism().map_or(1, |n| n.get())];
+    threads.sort_unstable();
+    threads.dedup();
+    for t in threads {
+        let start = Instant::now();
+        let (ret, comps) = solve_by_components(&graph, min_cost_max_matching, t);
+        println!("{} components, {} threads: {} ms, cost {}, {} pairs", comps, t, start.elapsed().as_millis(), ret.cost, ret.size());
+        if ret.cost != whole.cost || ret.size() != whole.size() {
+            println!("Components plan differs from the whole graph one");
+        }
+    }
+}
+
+// Parallel auction by number of threads, speed-up against one thread
+pub fn bench_threads(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) {
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    let start = Instant::now();
+    let exact = min_cost_max_matching(&graph);
+    println!("Shortest paths: {} ms, cost {}; {} cores", start.elapsed().as_millis(), exact.cost,
+        std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let mut single: Option<u128> = None;
+    for threads in THREAD_COUNTS {
+        let start = Instant::now();
+        let ret = auction(&graph, threads);
+        let elapsed = start.elapsed().as_millis();
+        let base = *single.get_or_insert(elapsed);
+        println!("Auction, {} threads: {} ms, speed-up {:.2}, cost {}", threads, elapsed, base as f32 / elapsed.max(1) as f32, ret.cost);
+        if ret.cost != exact.cost || ret.size() != exact.size() {
+            println!("Auction plan differs: cost {} size {}, exact {} size {}", ret.cost, ret.size(), exact.cost, exact.size());
+        }
+    }
+}
+
+// costs of a cab (order) to the given orders (cabs), forbidden pairs left out
+fn random_edges(rng: &mut impl Rng, ids: &[usize]) -> Vec<(usize, u32)> {
+    let mut edges: Vec<(usize, u32)> = vec![];
+    for id in ids {
+        if rng.gen_ratio(FORBIDDEN_PCT, 100) {
+            continue;
+        }
+        edges.push((*id, rng.gen_range(MIN_VALUE..MAX_VALUE) as u32));
+    }
+    edges
+}
+
+// the instance a dispatcher would solve from scratch, without the slots of removed cabs and orders
+fn live_graph(graph: &Graph, rows: &[usize], cols: &[usize]) -> Graph {
+    let mut col_idx: Vec<Option<usize>> = vec![None; graph.cols];
+    for (i, d) in cols.iter().enumerate() {
+        col_idx[*d] = Some(i);
+    }
+    let adj = rows.iter().map(|s| graph.adj[*s].iter().filter_map(|(d, c)| col_idx[*d].map(|i| (i, *c))).collect()).collect();
+    Graph { rows: rows.len(), cols: cols.len(), adj }
+}
+
+// Runs per instance of a solver in the adaptive mode, (minimum, maximum)
+fn repetitions(key: &Solvers) -> (usize, usize) {
+    if SLOW_SOLVERS.contains(key) { SLOW_REPEATS } else { ADAPTIVE_REPEATS }
+}
+
+// The solvers of the main loop on MAX_ITER instances, each repeated until the confidence interval of
+// its median time is narrower than TARGET_CI_PCT (or one millisecond, the resolution of the timings).
+// Rounds run the solvers still going in a random order, like the main loop.
+pub fn bench_adaptive(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &mut Forbidden, s_size: usize, d_size: usize) {
+    let mut samples = Samples::new();
+    let mut rng = rand::thread_rng();
+    let start = Instant::now();
+    results::open(RESULTS_FILE, instance_class(s_size, d_size, &COSTS));
+    console::start(0);
+    for iter in 0..MAX_ITER {
+        console::stage(format!("Instance {} of {}", iter, MAX_ITER));
+        results::set_instance(Some(iter));
+        let munk_cost = new_instance(cost_arr, forb, s_size, d_size, &COSTS, &mut rng);
+        let first: Vec<usize> = samples.time.iter().map(|t| t.len()).collect();
+        let mut active: Vec<Solvers> = COMPARED.to_vec();
+        while !active.is_empty() {
+            active.shuffle(&mut rng);
+            for key in active.iter() {
+                run_timed(key, munk_cost, d_size, s_size, cost_arr, forb, &mut samples);
+            }
+            let over = start.elapsed() > TIME_BUDGET;
+            active.retain(|key| {
+                let (min, max) = repetitions(key);
+                let times = &samples.time[key.clone() as usize][first[key.clone() as usize]..];
+                times.len() < min || (times.len() < max && !over && !narrow(times))
+            });
+        }
+        for key in COMPARED.iter() {
+            let name: &'static str = key.clone().into();
+            let times = &samples.time[key.clone() as usize][first[key.clone() as usize]..];
+            let ci = median_ci(times).map_or("-".to_string(), |(lo, hi)| format!("[{}, {}]", lo, hi));
+            println!("{}: {} runs, median {} ms, 95% CI {}{}", name, times.len(), median(times), ci,
+                if narrow(times) { "" } else { ", target not reached" });
+        }
+    }
+    console::finish();
+    if start.elapsed() > TIME_BUDGET {
+        println!("Time budget of {} s spent after {} s", TIME_BUDGET.as_secs(), start.elapsed().as_secs());
+    }
+    print_summary(&samples, &COMPARED, false); // solvers repeat a different number of times per instance
+}
+
+fn narrow(times: &[u128]) -> bool {
+    median_ci(times).is_some_and(|(lo, hi)| hi - lo <= 1 || (hi - lo) as f64 <= median(times) * TARGET_CI_PCT / 100.0)
+}
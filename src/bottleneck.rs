@@ -0,0 +1,113 @@
+use crate::matching::{hopcroft_karp, min_cost_max_matching, Assignment, Graph};
+
+// Linear bottleneck assignment: of the largest plans, one whose most expensive pair is as cheap
+// as possible (the longest pickup, for fairness). Binary search over the distinct costs for the
+// lowest threshold at which Hopcroft-Karp still finds as many pairs as on the whole graph.
+// Returns the bottleneck (None when no pair can be made at all) and the plan found at it.
+pub fn bottleneck(graph: &Graph) -> (Option<u32>, Assignment) {
+    match threshold(graph) {
+        Some((limit, row_to_col)) => (Some(limit), Assignment::new(row_to_col, graph.cols, graph)),
+        None => (None, Assignment::new(vec![None; graph.rows], graph.cols, graph)),
+    }
+}
+
+// Lexicographic: the bottleneck first, then the cheapest of the largest plans that keep to it
+pub fn bottleneck_min_sum(graph: &Graph) -> (Option<u32>, Assignment) {
+    match threshold(graph) {
+        Some((limit, _)) => {
+            let ret = min_cost_max_matching(&at_most(graph, limit));
+            (Some(limit), Assignment::new(ret.row_to_col, graph.cols, graph))
+        }
+        None => (None, Assignment::new(vec![None; graph.rows], graph.cols, graph)),
+    }
+}
+
+fn threshold(graph: &Graph) -> Option<(u32, Vec<Option<usize>>)> {
+    let (max_size, _) = hopcroft_karp(graph);
+    if max_size == 0 {
+        return None;
+    }
+    let mut costs: Vec<u32> = graph.adj.iter().flatten().map(|(_, c)| *c).collect();
+    costs.sort_unstable();
+    costs.dedup();
+    // the largest plan is there at the highest cost, so the answer is in costs[lo..=hi]
+    let (mut lo, mut hi) = (0, costs.len() - 1);
+    let mut best: Option<Vec<Option<usize>>> = None; // plan at costs[hi], if already found
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let (size, row_match) = hopcroft_karp(&at_most(graph, costs[mid]));
+        if size == max_size {
+            hi = mid;
+            best = Some(row_match);
+        } else {
+            lo = mid + 1;
+        }
+    }
+    let row_to_col = best.unwrap_or_else(|| hopcroft_karp(&at_most(graph, costs[hi])).1);
+    Some((costs[lo], row_to_col))
+}
+
+// the graph without the pairs above 'limit'
+fn at_most(graph: &Graph, limit: u32) -> Graph {
+    let adj = graph.adj.iter().map(|edges| edges.iter().filter(|(_, c)| *c <= limit).copied().collect()).collect();
+    Graph { rows: graph.rows, cols: graph.cols, adj }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{Assignment, Graph};
+    use crate::{test_cost_arr, test_instance};
+    use super::{bottleneck, bottleneck_min_sum};
+
+    // (size, longest pair, cost) of every plan, each row given a free allowed column or none
+    fn plans(graph: &Graph, s: usize, used: &mut Vec<bool>, plan: (usize, u32, u64), ret: &mut Vec<(usize, u32, u64)>) {
+        if s == graph.rows {
+            ret.push(plan);
+            return;
+        }
+        plans(graph, s + 1, used, plan, ret);
+        for (d, c) in &graph.adj[s] {
+            if !used[*d] {
+                used[*d] = true;
+                plans(graph, s + 1, used, (plan.0 + 1, plan.1.max(*c), plan.2 + *c as u64), ret);
+                used[*d] = false;
+            }
+        }
+    }
+
+    fn longest(plan: &Assignment, graph: &Graph) -> Option<u32> {
+        plan.row_to_col.iter().enumerate().filter_map(|(s, d)| d.and_then(|d| graph.cost(s, d))).max()
+    }
+
+    #[test]
+    fn bottleneck_matches_brute_force() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(15);
+        for (s_size, d_size, forbidden_pct) in [(1, 1, 0), (4, 4, 0), (5, 5, 30), (3, 6, 20), (6, 3, 20), (6, 6, 60)] {
+            for max in [3, 50] {
+                for _ in 0..5 {
+                    let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, max, forbidden_pct);
+                    let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                    let mut all = vec![];
+                    plans(&graph, 0, &mut vec![false; d_size], (0, 0, 0), &mut all);
+                    let max_size = all.iter().map(|p| p.0).max().unwrap();
+                    let largest: Vec<&(usize, u32, u64)> = all.iter().filter(|p| p.0 == max_size).collect();
+                    let limit = largest.iter().map(|p| p.1).min().unwrap();
+                    let cost = largest.iter().filter(|p| p.1 == limit).map(|p| p.2).min().unwrap();
+                    let limit = (max_size > 0).then_some(limit);
+                    let what = format!("{}x{}, costs 0..={}, {}% forbidden", s_size, d_size, max, forbidden_pct);
+
+                    let (found, plan) = bottleneck(&graph);
+                    assert_eq!(found, limit, "{}", what);
+                    assert_eq!((plan.size(), longest(&plan, &graph)), (max_size, limit), "{}", what);
+
+                    let (found, plan) = bottleneck_min_sum(&graph);
+                    assert_eq!(found, limit, "{}", what);
+                    assert_eq!((plan.size(), longest(&plan, &graph), plan.cost), (max_size, limit, cost), "{}", what);
+                }
+            }
+        }
+    }
+}
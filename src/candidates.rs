@@ -0,0 +1,189 @@
+use crate::forbidden::Forbidden;
+use crate::matching::{hopcroft_karp, min_cost_max_matching_warm, Assignment, Graph, WarmStart};
+use crate::{BIG_VALUE, DSIZE, SSIZE};
+
+// Where the costs of a candidate graph come from: the dense matrix (forbidden pairs and padding
+// are not allowed) or cab and order coordinates, a pair costing its rounded Euclidean distance
+pub enum Costs<'a> {
+    Matrix { cost_arr: &'a [[u16; DSIZE]; SSIZE], forb: &'a Forbidden, s_size: usize, d_size: usize },
+    Points { cabs: &'a [(f64, f64)], orders: &'a [(f64, f64)] },
+}
+
+impl Costs<'_> {
+    pub fn rows(&self) -> usize {
+        match self {
+            Costs::Matrix { s_size, .. } => *s_size,
+            Costs::Points { cabs, .. } => cabs.len(),
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        match self {
+            Costs::Matrix { d_size, .. } => *d_size,
+            Costs::Points { orders, .. } => orders.len(),
+        }
+    }
+
+    pub fn cost(&self, s: usize, d: usize) -> Option<u32> {
+        match self {
+            Costs::Matrix { cost_arr, forb, .. } => {
+                (!forb.is_forbidden(s, d) && cost_arr[s][d] < BIG_VALUE).then_some(cost_arr[s][d] as u32)
+            }
+            Costs::Points { cabs, orders } => {
+                let (dx, dy) = (cabs[s].0 - orders[d].0, cabs[s].1 - orders[d].1);
+                Some((dx * dx + dy * dy).sqrt().round() as u32)
+            }
+        }
+    }
+
+    // every allowed pair, for the exact solvers to compare with
+    pub fn full_graph(&self) -> Graph {
+        let adj = (0..self.rows()).map(|s| (0..self.cols()).filter_map(|d| self.cost(s, d).map(|c| (d, c))).collect()).collect();
+        Graph { rows: self.rows(), cols: self.cols(), adj }
+    }
+}
+
+// The k cheapest columns of every row, and the k cheapest rows of every column so that no
+// order is left without a cab to compete for it; edges of a row are in column order
+pub fn candidate_graph(costs: &Costs, k: usize) -> Graph {
+    let (rows, cols) = (costs.rows(), costs.cols());
+    let mut keep: Vec<Vec<usize>> = vec![vec![]; rows];
+    for (s, kept) in keep.iter_mut().enumerate() {
+        *kept = nearest((0..cols).filter_map(|d| costs.cost(s, d).map(|c| (c, d))).collect(), k);
+    }
+    for d in 0..cols {
+        for s in nearest((0..rows).filter_map(|s| costs.cost(s, d).map(|c| (c, s))).collect(), k) {
+            keep[s].push(d);
+        }
+    }
+    let adj = keep.into_iter().enumerate().map(|(s, mut kept)| {
+        kept.sort_unstable();
+        kept.dedup();
+        kept.into_iter().map(|d| (d, costs.cost(s, d).unwrap())).collect()
+    }).collect();
+    Graph { rows, cols, adj }
+}
+
+// ids of the k cheapest (cost, id), ties to the lower id
+fn nearest(cells: Vec<(u32, usize)>, k: usize) -> Vec<usize> {
+    nearest_by(cells, k)
+}
+
+fn nearest_by<T: Ord>(mut cells: Vec<(T, usize)>, k: usize) -> Vec<usize> {
+    if cells.len() > k && k > 0 {
+        cells.select_nth_unstable(k - 1);
+        cells.truncate(k);
+    } else if k == 0 {
+        cells.clear();
+    }
+    cells.into_iter().map(|(_, id)| id).collect()
+}
+
+const ADDED_PER_ROW: usize = 10; // most violated pairs a row gets back in a round
+
+pub struct Certified {
+    pub plan: Assignment,
+    pub rounds: usize, // solves of the candidate graph
+    pub added: usize,  // edges put back because the certificate failed
+}
+
+// Solves the candidate graph and proves the plan optimal on the full costs with the potentials
+// of the sparse solve: a plan of a given size is cheapest when no pair has a negative
+// reduced cost (cost + row_pot - col_pot), and the plan has to be as large as the full graph allows.
+// Pairs that break the first are added, the most violated ones first; when the full graph has a larger
+// plan, free rows get their cheapest pairs to free columns (everything if that does not help).
+// Then it is solved again from the last potentials until the check passes.
+pub fn solve_certified(costs: &Costs, mut graph: Graph) -> Certified {
+    let (rows, cols) = (costs.rows(), costs.cols());
+    let mut hint = WarmStart::default();
+    let mut rounds: usize = 0;
+    let mut added: usize = 0;
+    loop {
+        let (plan, next) = min_cost_max_matching_warm(&graph, &hint);
+        rounds += 1;
+        let (row_pot, col_pot) = (next.row_pot.as_ref().unwrap(), next.col_pot.as_ref().unwrap());
+        let mut missing: Vec<Vec<(usize, u32)>> = vec![vec![]; rows];
+        for (s, edges) in missing.iter_mut().enumerate() {
+            let mut violated: Vec<(i64, usize)> = vec![];
+            for (d, pot) in col_pot.iter().enumerate() {
+                if let Some(c) = costs.cost(s, d) {
+                    let reduced = c as i64 + row_pot[s] - pot;
+                    if reduced < 0 {
+                        violated.push((reduced, d));
+                    }
+                }
+            }
+            // the worst ones, the potentials of the next round may well be fine with the rest
+            *edges = nearest_by(violated, ADDED_PER_ROW).into_iter().map(|d| (d, costs.cost(s, d).unwrap())).collect();
+        }
+        let short = plan.size() < rows.min(cols) && hopcroft_karp(&costs.full_graph()).0 > plan.size();
+        if short {
+            let free_cols = plan.unassigned_cols();
+            for s in plan.unassigned_rows() {
+                let cells = free_cols.iter().filter_map(|d| costs.cost(s, *d).map(|c| (c, *d))).collect();
+                missing[s].extend(nearest(cells, ADDED_PER_ROW).into_iter().map(|d| (d, costs.cost(s, d).unwrap())));
+            }
+        }
+        let mut changed = false;
+        for (s, edges) in missing.into_iter().enumerate() {
+            for (d, c) in edges {
+                if let Err(at) = graph.adj[s].binary_search_by_key(&d, |(col, _)| *col) {
+                    graph.adj[s].insert(at, (d, c));
+                    added += 1;
+                    changed = true;
+                }
+            }
+        }
+        if short && !changed {
+            // nothing left to add between free rows and free columns: the rest is needed too
+            let full = costs.full_graph();
+            added += full.adj.iter().map(|e| e.len()).sum::<usize>() - graph.adj.iter().map(|e| e.len()).sum::<usize>();
+            graph = full;
+            changed = true;
+        }
+        if !changed {
+            return Certified { plan, rounds, added };
+        }
+        hint = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::matching::min_cost_max_matching;
+    use crate::{test_cost_arr, test_instance};
+    use super::{candidate_graph, solve_certified, Costs};
+
+    // the certified plan is a plan of the full costs, as large and as cheap as the full solve
+    fn check(costs: &Costs, what: &str) {
+        let full = min_cost_max_matching(&costs.full_graph());
+        for k in 0..=2 {
+            let ret = solve_certified(costs, candidate_graph(costs, k));
+            let pairs: Vec<(usize, usize)> = ret.plan.row_to_col.iter().enumerate().filter_map(|(s, d)| d.map(|d| (s, d))).collect();
+            assert!(pairs.iter().all(|(s, d)| ret.plan.col_to_row[*d] == Some(*s) && costs.cost(*s, *d).is_some()), "{}, k {}", what, k);
+            assert_eq!(pairs.iter().map(|(s, d)| costs.cost(*s, *d).unwrap() as u64).sum::<u64>(), ret.plan.cost, "{}, k {}", what, k);
+            assert_eq!((ret.plan.size(), ret.plan.cost), (full.size(), full.cost), "{}, k {}", what, k);
+        }
+    }
+
+    #[test]
+    fn certified_solve_matches_full_solve() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(16);
+        for (s_size, d_size, forbidden_pct) in [(8, 8, 0), (12, 12, 30), (6, 15, 20), (15, 6, 40), (10, 10, 80)] {
+            for _ in 0..5 {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 100, forbidden_pct);
+                check(&Costs::Matrix { cost_arr: &cost_arr, forb: &forb, s_size, d_size }, &format!("{}x{} matrix", s_size, d_size));
+            }
+        }
+        for (cabs, orders) in [(10, 10), (6, 14), (14, 6)] {
+            for _ in 0..5 {
+                let cabs: Vec<(f64, f64)> = (0..cabs).map(|_| (rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0))).collect();
+                let orders: Vec<(f64, f64)> = (0..orders).map(|_| (rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0))).collect();
+                check(&Costs::Points { cabs: &cabs, orders: &orders }, &format!("{} cabs, {} orders", cabs.len(), orders.len()));
+            }
+        }
+    }
+}
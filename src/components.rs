@@ -0,0 +1,128 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use crate::matching::{Assignment, Graph};
+
+// Rows and columns of one connected component of the allowed pairs, in the original indices
+pub struct Component {
+    pub rows: Vec<usize>,
+    pub cols: Vec<usize>,
+}
+
+// Components with at least one allowed pair, largest first; rows and columns without any
+// pair are in none of them and stay unassigned
+pub fn components(graph: &Graph) -> Vec<Component> {
+    let mut col_rows: Vec<Vec<usize>> = vec![vec![]; graph.cols];
+    for (s, edges) in graph.adj.iter().enumerate() {
+        for (d, _) in edges {
+            col_rows[*d].push(s);
+        }
+    }
+    let mut row_seen: Vec<bool> = vec![false; graph.rows];
+    let mut col_seen: Vec<bool> = vec![false; graph.cols];
+    let mut ret: Vec<Component> = vec![];
+    for root in 0..graph.rows {
+        if row_seen[root] || graph.adj[root].is_empty() {
+            continue;
+        }
+        let mut comp = Component { rows: vec![], cols: vec![] };
+        let mut queue: VecDeque<usize> = VecDeque::from([root]);
+        row_seen[root] = true;
+        while let Some(s) = queue.pop_front() {
+            comp.rows.push(s);
+            for (d, _) in &graph.adj[s] {
+                if col_seen[*d] {
+                    continue;
+                }
+                col_seen[*d] = true;
+                comp.cols.push(*d);
+                for s2 in &col_rows[*d] {
+                    if !row_seen[*s2] {
+                        row_seen[*s2] = true;
+                        queue.push_back(*s2);
+                    }
+                }
+            }
+        }
+        comp.rows.sort_unstable();
+        comp.cols.sort_unstable();
+        ret.push(comp);
+    }
+    ret.sort_by_key(|c| std::cmp::Reverse(c.rows.len() * c.cols.len()));
+    ret
+}
+
+// Solves every component on its own with 'exact' and puts the plans together in the original indices.
+// With more than one thread the components are handed out largest first to whichever thread is free.
+pub fn solve_by_components(graph: &Graph, exact: fn(&Graph) -> Assignment, threads: usize) -> (Assignment, usize) {
+    let comps = components(graph);
+    let row_to_col: Mutex<Vec<Option<usize>>> = Mutex::new(vec![None; graph.rows]);
+    let next = AtomicUsize::new(0);
+    let work = || loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        let Some(comp) = comps.get(i) else {
+            break;
+        };
+        let ret = exact(&subgraph(graph, comp));
+        let mut row_to_col = row_to_col.lock().unwrap();
+        for (i, d) in ret.row_to_col.iter().enumerate() {
+            row_to_col[comp.rows[i]] = d.map(|d| comp.cols[d]);
+        }
+    };
+    if threads <= 1 {
+        work();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..threads.min(comps.len()) {
+                scope.spawn(work);
+            }
+        });
+    }
+    (Assignment::new(row_to_col.into_inner().unwrap(), graph.cols, graph), comps.len())
+}
+
+// the component renumbered from 0
+fn subgraph(graph: &Graph, comp: &Component) -> Graph {
+    let mut col_idx: Vec<usize> = vec![usize::MAX; graph.cols];
+    for (i, d) in comp.cols.iter().enumerate() {
+        col_idx[*d] = i;
+    }
+    let adj = comp.rows.iter().map(|s| graph.adj[*s].iter().map(|(d, c)| (col_idx[*d], *c)).collect()).collect();
+    Graph { rows: comp.rows.len(), cols: comp.cols.len(), adj }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{min_cost_max_matching, Graph};
+    use crate::{test_cost_arr, test_instance};
+    use super::solve_by_components;
+
+    // pairs across blocks are forbidden, so that there are several components to hand out
+    #[test]
+    fn components_match_full_solve() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(17);
+        for (s_size, d_size, forbidden_pct, blocks) in [(8, 8, 0, 1), (12, 12, 20, 3), (9, 15, 30, 3), (16, 10, 50, 4), (12, 12, 90, 2)] {
+            for _ in 0..5 {
+                let mut forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 50, forbidden_pct);
+                for s in 0..s_size {
+                    for d in (0..d_size).filter(|d| d % blocks != s % blocks) {
+                        forb.forbid(s, d);
+                    }
+                }
+                let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                let full = min_cost_max_matching(&graph);
+                for threads in [1, 4] {
+                    let (plan, _) = solve_by_components(&graph, min_cost_max_matching, threads);
+                    let what = format!("{}x{}, {} blocks, {} threads", s_size, d_size, blocks, threads);
+                    assert!(plan.row_to_col.iter().enumerate().all(|(s, d)| d.is_none_or(|d| plan.col_to_row[d] == Some(s) && graph.cost(s, d).is_some())),
+                        "{}", what);
+                    assert_eq!((plan.size(), plan.cost), (full.size(), full.cost), "{}", what);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::io::{self, IsTerminal};
+use std::sync::Mutex;
+use std::time::Instant;
+use chrono::Utc;
+use serde::Deserialize;
+use crate::{Solvers, VERBOSITY};
+
+// How much is printed while solvers run: Quiet only what went wrong and the summary, Normal also
+// the progress, Verbose what every run found (best costs, gaps of the heuristics...), Matrix every cost matrix too.
+// Set in VERBOSITY or in an experiment file (see experiment.rs).
+#[derive(PartialEq, PartialOrd, Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+    Matrix,
+}
+
+static LEVEL: Mutex<Verbosity> = Mutex::new(VERBOSITY);
+
+pub fn set_verbosity(level: Verbosity) {
+    *LEVEL.lock().unwrap() = level;
+}
+
+pub fn at(level: Verbosity) -> bool {
+    *LEVEL.lock().unwrap() >= level
+}
+
+// What a run found out, printed when Verbose
+pub fn info(msg: String) {
+    if at(Verbosity::Verbose) {
+        clear();
+        println!("{}", msg);
+    }
+}
+
+// Something wrong with a run, always printed
+pub fn note(msg: String) {
+    clear();
+    println!("{}", msg);
+}
+
+// The progress of a long run: a line per stage (iteration or instance) and, on a terminal, a status line
+// on stderr while a solver runs, cleared for anything printed meanwhile and after the run
+struct Progress {
+    start: Instant,
+    total: usize, // runs expected, 0 if not known in advance (adaptive mode)
+    done: usize,
+    stage: String,
+    live: bool,
+    shown: bool, // the status line is on the screen
+}
+
+static PROGRESS: Mutex<Option<Progress>> = Mutex::new(None);
+
+pub fn start(total: usize) {
+    *PROGRESS.lock().unwrap() = Some(Progress { start: Instant::now(), total, done: 0, stage: String::new(),
+        live: io::stderr().is_terminal(), shown: false });
+}
+
+pub fn stage(stage: String) {
+    clear();
+    if let Some(p) = PROGRESS.lock().unwrap().as_mut() {
+        if at(Verbosity::Normal) {
+            println!("{} start: {:?}, {}", stage, Utc::now(), p.eta());
+        }
+        p.stage = stage;
+    }
+}
+
+// before every timed run
+pub fn run(key: &Solvers) {
+    if !at(Verbosity::Normal) {
+        return;
+    }
+    if let Some(p) = PROGRESS.lock().unwrap().as_mut().filter(|p| p.live) {
+        let name: &'static str = key.clone().into();
+        let runs = if p.total > 0 { format!("{}/{}", p.done + 1, p.total) } else { (p.done + 1).to_string() };
+        eprint!("\r\x1b[K{} | run {} {} | {}", p.stage, runs, name, p.eta());
+        p.shown = true;
+    }
+}
+
+// after every timed run
+pub fn done() {
+    clear();
+    if let Some(p) = PROGRESS.lock().unwrap().as_mut() {
+        p.done += 1;
+    }
+}
+
+pub fn finish() {
+    clear();
+    if let Some(p) = PROGRESS.lock().unwrap().take() {
+        println!("{} runs in {}", p.done, hms(p.start.elapsed().as_secs()));
+    }
+}
+
+fn clear() {
+    if let Some(p) = PROGRESS.lock().unwrap().as_mut().filter(|p| p.shown) {
+        eprint!("\r\x1b[K");
+        p.shown = false;
+    }
+}
+
+impl Progress {
+    // by the mean time of the runs so far, the solvers take turns so every one is in it
+    fn eta(&self) -> String {
+        let elapsed = self.start.elapsed().as_secs();
+        if self.done == 0 || self.total <= self.done {
+            return format!("{} elapsed", hms(elapsed));
+        }
+        let left = elapsed as f64 / self.done as f64 * (self.total - self.done) as f64;
+        format!("{} elapsed, ETA {}", hms(elapsed), hms(left as u64))
+    }
+}
+
+fn hms(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+const CHART_WIDTH: usize = 50;
+
+// A bar per solver, the fastest first: '#' up to the median, '-' on to the slowest run
+pub fn chart(rows: &[(&str, f64, u128)]) {
+    let Some(longest) = rows.iter().map(|(_, _, max)| *max).max().filter(|m| *m > 0) else {
+        return;
+    };
+    let mut sorted = rows.to_vec();
+    sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+    println!("Median time per solver, '-' up to the slowest run ({} ms = {} characters):", longest, CHART_WIDTH);
+    for (name, median, max) in sorted {
+        let bar = (median * CHART_WIDTH as f64 / longest as f64).round() as usize;
+        let whisker = (max as usize * CHART_WIDTH).div_ceil(longest as usize).max(bar);
+        println!("{:<10} {}{}{} {} ms", name, "#".repeat(bar), "-".repeat(whisker - bar), " ".repeat(CHART_WIDTH - whisker), median);
+    }
+}
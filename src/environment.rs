@@ -0,0 +1,82 @@
+use std::fs;
+use std::process::Command;
+use std::thread;
+
+// The versions of what compiled the binary are taken as they were at build time: Cargo.lock and rustc (see build.rs)
+const CARGO_LOCK: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock"));
+const PACKAGE: &str = env!("CARGO_PKG_NAME");
+const RUSTC_VERSION: &str = env!("RUSTC_VERSION");
+// what glpk.py and munk*.py import
+const PYTHON_PACKAGES: [&str; 6] = ["numpy", "scipy", "laptools", "cvxopt", "munkres", "lapjv"];
+
+// Where the numbers of a report come from, printed at its top: results from different machines
+// (Mac and Ubuntu so far) are not comparable without it
+pub fn describe() -> String {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut ret = String::new();
+    ret += &format!("CPU: {}, {} cores\n", cpu_model(), cores);
+    ret += &format!("OS: {}, {}\n", os_name(), first_line("uname -srm"));
+    ret += &format!("rustc: {}\n", RUSTC_VERSION);
+    ret += &format!("cc: {}, gcc: {}, g++: {}\n", first_line("cc --version"), first_line("gcc --version"), first_line("g++ --version"));
+    ret += &format!("Python: {}\n", first_line("python3 --version"));
+    ret += &format!("Python packages: {}\n", python_packages());
+    ret += &format!("Crates: {}\n", crate_versions().join(", "));
+    let dirty = !first_line("git status --porcelain --untracked-files=no").is_empty();
+    ret += &format!("Commit: {}{}\n", first_line("git rev-parse --short HEAD"), if dirty { " with local changes" } else { "" });
+    ret
+}
+
+// first line of the output of 'sh -c cmd', empty if it could not run
+fn first_line(cmd: &str) -> String {
+    match Command::new("sh").arg("-c").arg(format!("{} 2>/dev/null", cmd)).output() {
+        Ok(out) => String::from_utf8_lossy(&out.stdout).lines().next().unwrap_or("").trim().to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+fn cpu_model() -> String {
+    if cfg!(target_os = "macos") {
+        return first_line("sysctl -n machdep.cpu.brand_string");
+    }
+    let info = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    info.lines().find(|l| l.starts_with("model name")).and_then(|l| l.split(':').nth(1)).unwrap_or("unknown").trim().to_string()
+}
+
+fn os_name() -> String {
+    if cfg!(target_os = "macos") {
+        return format!("macOS {}", first_line("sw_vers -productVersion"));
+    }
+    let release = fs::read_to_string("/etc/os-release").unwrap_or_default();
+    release.lines().find_map(|l| l.strip_prefix("PRETTY_NAME=")).map_or("unknown".to_string(), |n| n.trim_matches('"').to_string())
+}
+
+fn python_packages() -> String {
+    let script = format!("import importlib.metadata as m\nfor p in {:?}:\n    try: print(p, m.version(p))\n    except Exception: print(p, '-')",
+        PYTHON_PACKAGES);
+    match Command::new("python3").arg("-c").arg(script).output() {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout).lines().collect::<Vec<&str>>().join(", "),
+        _ => "-".to_string(),
+    }
+}
+
+// the dependencies of this crate with the versions in Cargo.lock
+fn crate_versions() -> Vec<String> {
+    let packages: Vec<&str> = CARGO_LOCK.split("[[package]]").collect();
+    let field = |package: &str, key: &str| -> String {
+        package.lines().find_map(|l| l.strip_prefix(key)).map_or(String::new(), |v| v.trim_matches('"').to_string())
+    };
+    let Some(own) = packages.iter().find(|p| field(p, "name = ") == PACKAGE) else {
+        return vec![];
+    };
+    let deps: Vec<String> = own.lines().skip_while(|l| !l.starts_with("dependencies")).skip(1).take_while(|l| !l.starts_with(']'))
+        .map(|l| l.trim().trim_end_matches(',').trim_matches('"').to_string()).collect();
+    deps.iter().map(|dep| {
+        // "name version" when Cargo.lock has more than one version of it
+        let (name, version) = dep.split_once(' ').unwrap_or((dep, ""));
+        if !version.is_empty() {
+            return format!("{} {}", name, version);
+        }
+        let found = packages.iter().find(|p| field(p, "name = ") == name).map(|p| field(p, "version = "));
+        format!("{} {}", name, found.unwrap_or_default())
+    }).collect()
+}
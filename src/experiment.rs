@@ -0,0 +1,305 @@
+use std::cmp;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::Deserialize;
+use crate::console::{self, Verbosity};
+use crate::hybrid::HybridLimit;
+use crate::isolation::in_process;
+use crate::report::{self, Plan};
+use crate::{results, CostRange, Solvers, BIG_VALUE, COMPARED, DSIZE, GLPK_MAX_SIZE, HYBRID_LIMIT, ISOLATED, K_BEST, MAX_ITER, REPEATS,
+    RESULTS_FILE, SOLVERS, SSIZE, VERBOSITY, WARMUP_ITER};
+
+// An experiment defined in a TOML file rather than in the consts of main.rs ("experiment FILE", examples
+// in experiments/): instance generators, the solvers with their options and timeouts, the repetitions and
+// where the results go. What is left out of the file keeps the value of the const. The whole file is
+// checked before anything runs.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Experiment {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default = "default_verbosity")]
+    verbosity: Verbosity,
+    #[serde(default = "default_isolated")]
+    isolated: bool, // see isolation.rs, needed for timeouts of in-crate solvers
+    #[serde(default)]
+    runs: Runs,
+    #[serde(default)]
+    output: Output,
+    generators: Vec<Generator>,
+    solvers: Vec<Solver>,
+}
+
+fn default_verbosity() -> Verbosity {
+    VERBOSITY
+}
+
+fn default_isolated() -> bool {
+    ISOLATED
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct Runs {
+    warmup: usize, // instances per size not counted
+    instances: usize, // per size
+    repeats: usize, // runs of every solver per instance
+}
+
+impl Default for Runs {
+    fn default() -> Runs {
+        Runs { warmup: WARMUP_ITER, instances: MAX_ITER, repeats: REPEATS }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, default)]
+struct Output {
+    results: String, // every run, see results.rs
+    report: Option<String>, // Markdown tables, with a chart per generator of several sizes next to it
+}
+
+impl Default for Output {
+    fn default() -> Output {
+        Output { results: RESULTS_FILE.to_string(), report: None }
+    }
+}
+
+// Random instances of the sizes, costs drawn as in CostRange. With a seed they are the same in every run.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Generator {
+    name: String,
+    sizes: Vec<(usize, usize)>, // supply x demand
+    min: u16,
+    max: u16,
+    #[serde(default)]
+    zero_pct: u32,
+    #[serde(default)]
+    forbidden_pct: u32,
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Solver {
+    name: String,
+    timeout: Option<f64>, // seconds, for solvers in a process of their own
+    k_best: Option<usize>, // MURTY
+    threshold: Option<u16>, // HYBRID, see HybridLimit
+    budget: Option<usize>, // HYBRID
+    max_size: Option<usize>, // GLPK, larger instances are not run (see NETSIMPLEX)
+}
+
+// What the experiment changes of the consts, read by the solvers; isolated runs load the file again
+struct Settings {
+    file: Option<String>,
+    isolated: bool,
+    k_best: usize,
+    hybrid_limit: HybridLimit,
+    glpk_max_size: usize,
+    timeouts: [Option<Duration>; SOLVERS],
+}
+
+static SETTINGS: Mutex<Settings> = Mutex::new(Settings { file: None, isolated: ISOLATED, k_best: K_BEST, hybrid_limit: HYBRID_LIMIT,
+    glpk_max_size: GLPK_MAX_SIZE, timeouts: [None; SOLVERS] });
+
+pub fn file() -> Option<String> {
+    SETTINGS.lock().unwrap().file.clone()
+}
+
+pub fn isolated() -> bool {
+    SETTINGS.lock().unwrap().isolated
+}
+
+pub fn k_best() -> usize {
+    SETTINGS.lock().unwrap().k_best
+}
+
+pub fn hybrid_limit() -> HybridLimit {
+    SETTINGS.lock().unwrap().hybrid_limit
+}
+
+pub fn glpk_max_size() -> usize {
+    SETTINGS.lock().unwrap().glpk_max_size
+}
+
+pub fn timeout(key: &Solvers) -> Option<Duration> {
+    SETTINGS.lock().unwrap().timeouts[key.clone() as usize]
+}
+
+// How the runs of a plan are made, set by run_scenario; solvers not in 'timeouts' run to the end
+pub fn set_runs(isolated: bool, timeouts: &[(Solvers, Duration)]) {
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.isolated = isolated;
+    settings.timeouts = [None; SOLVERS];
+    for (key, timeout) in timeouts {
+        settings.timeouts[key.clone() as usize] = Some(*timeout);
+    }
+}
+
+fn solver_key(name: &str) -> Option<Solvers> {
+    COMPARED.iter().find(|s| <&'static str>::from((*s).clone()) == name).cloned()
+}
+
+fn load(filename: &str) -> Result<Experiment, Vec<String>> {
+    let text = fs::read_to_string(filename).map_err(|e| vec![format!("{}: {}", filename, e)])?;
+    let exp: Experiment = toml::from_str(&text).map_err(|e| vec![format!("{}: {}", filename, e)])?;
+    let errors = validate(&exp);
+    if errors.is_empty() { Ok(exp) } else { Err(errors) }
+}
+
+// Everything wrong with an experiment, in one go
+fn validate(exp: &Experiment) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+    if exp.runs.instances == 0 || exp.runs.repeats == 0 {
+        errors.push("runs: instances and repeats must be at least 1".to_string());
+    }
+    if exp.generators.is_empty() {
+        errors.push("no generators".to_string());
+    }
+    let mut names: HashSet<&str> = HashSet::new();
+    for gen in &exp.generators {
+        let at = format!("generator '{}'", gen.name);
+        // it is in the names of the charts
+        if gen.name.is_empty() || !gen.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            errors.push(format!("{}: names are letters, digits, '-' and '_'", at));
+        }
+        if !names.insert(&gen.name) {
+            errors.push(format!("{}: defined twice", at));
+        }
+        if gen.sizes.is_empty() {
+            errors.push(format!("{}: no sizes", at));
+        }
+        for (s_size, d_size) in &gen.sizes {
+            if *s_size == 0 || *d_size == 0 || cmp::max(s_size, d_size) > &cmp::min(SSIZE, DSIZE) {
+                errors.push(format!("{}: size {}x{} is not within 1..={}", at, s_size, d_size, cmp::min(SSIZE, DSIZE)));
+            }
+        }
+        if gen.min >= gen.max || gen.max > BIG_VALUE {
+            errors.push(format!("{}: costs are drawn from min..max, {}..{} is empty or above {}", at, gen.min, gen.max, BIG_VALUE));
+        }
+        if gen.zero_pct > 100 || gen.forbidden_pct > 100 {
+            errors.push(format!("{}: zero_pct and forbidden_pct are percentages", at));
+        }
+    }
+    if exp.solvers.is_empty() {
+        errors.push("no solvers".to_string());
+    }
+    let mut keys: Vec<Solvers> = vec![];
+    for solver in &exp.solvers {
+        let at = format!("solver '{}'", solver.name);
+        let Some(key) = solver_key(&solver.name) else {
+            let known: Vec<&'static str> = COMPARED.iter().map(|s| s.clone().into()).collect();
+            errors.push(format!("{}: unknown, solvers are {}", at, known.join(", ")));
+            continue;
+        };
+        if keys.contains(&key) {
+            errors.push(format!("{}: listed twice", at));
+        }
+        for (option, set, owner) in [("k_best", solver.k_best.is_some(), Solvers::Murty), ("threshold", solver.threshold.is_some(), Solvers::Hybrid),
+            ("budget", solver.budget.is_some(), Solvers::Hybrid), ("max_size", solver.max_size.is_some(), Solvers::GLPK)] {
+            if set && key != owner {
+                let name: &'static str = owner.into();
+                errors.push(format!("{}: {} is an option of {}", at, option, name));
+            }
+        }
+        if solver.threshold.is_some() && solver.budget.is_some() {
+            errors.push(format!("{}: threshold or budget, not both", at));
+        }
+        if solver.k_best == Some(0) {
+            errors.push(format!("{}: k_best must be at least 1", at));
+        }
+        match solver.timeout {
+            Some(t) if t <= 0.0 || !t.is_finite() => errors.push(format!("{}: timeout must be a number of seconds above 0", at)),
+            // an in-crate solver in this process cannot be stopped
+            Some(_) if !exp.isolated && in_process(&key) => errors.push(format!("{}: a timeout needs isolated = true", at)),
+            _ => {}
+        }
+        keys.push(key);
+    }
+    errors
+}
+
+fn apply(exp: &Experiment, filename: &str) {
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.file = Some(filename.to_string());
+    for solver in &exp.solvers {
+        settings.k_best = solver.k_best.unwrap_or(settings.k_best);
+        settings.glpk_max_size = solver.max_size.unwrap_or(settings.glpk_max_size);
+        if let Some(threshold) = solver.threshold {
+            settings.hybrid_limit = HybridLimit::Threshold(threshold);
+        }
+        if let Some(budget) = solver.budget {
+            settings.hybrid_limit = HybridLimit::Budget(budget);
+        }
+    }
+    console::set_verbosity(exp.verbosity);
+}
+
+// The settings of an experiment in an isolated run, false if the file cannot be used
+pub fn apply_file(filename: &str) -> bool {
+    match load(filename) {
+        Ok(exp) => {
+            apply(&exp, filename);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+// Checks and runs an experiment file, false if it is not valid
+pub fn run(filename: &str, cost_arr: &mut [[u16; DSIZE]; SSIZE]) -> bool {
+    let exp = match load(filename) {
+        Ok(exp) => exp,
+        Err(errors) => {
+            for error in errors {
+                println!("{}", error);
+            }
+            println!("{}: not run", filename);
+            return false;
+        }
+    };
+    apply(&exp, filename);
+    let solvers: Vec<Solvers> = exp.solvers.iter().filter_map(|s| solver_key(&s.name)).collect();
+    let timeouts: Vec<(Solvers, Duration)> = exp.solvers.iter()
+        .filter_map(|s| Some((solver_key(&s.name)?, Duration::from_secs_f64(s.timeout?))))
+        .collect();
+    let plan = Plan { solvers: &solvers, timeouts: &timeouts, isolated: exp.isolated, warmup: exp.runs.warmup, instances: exp.runs.instances, repeats: exp.runs.repeats };
+    println!("Experiment {}{}", exp.name, if exp.description.is_empty() { String::new() } else { format!(": {}", exp.description) });
+    for file in [Some(&exp.output.results), exp.output.report.as_ref()].into_iter().flatten() {
+        if let Some(dir) = Path::new(file).parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+        }
+    }
+    results::open(&exp.output.results, String::new());
+    console::start(plan.runs(exp.generators.iter().map(|g| g.sizes.len()).sum()));
+    let mut md = report::header(&exp.name, &format!("experiment {}", filename), &plan);
+    md += &format!("\n{}\n\n```toml\n{}```\n", exp.description, fs::read_to_string(filename).unwrap_or_default());
+    for gen in &exp.generators {
+        let costs = CostRange { min: gen.min, max: gen.max, zero_pct: gen.zero_pct, forbidden_pct: gen.forbidden_pct };
+        let mut rng = gen.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+        let cells: Vec<Vec<Option<report::Cell>>> = gen.sizes.iter()
+            .map(|(s_size, d_size)| report::run_scenario(cost_arr, *s_size, *d_size, &costs, &plan, &mut rng)).collect();
+        let Some(report_file) = &exp.output.report else {
+            continue;
+        };
+        md += &report::table(&gen.name, &gen.sizes, &[costs], &solvers, std::slice::from_ref(&cells));
+        if gen.sizes.len() > 1 {
+            let path = Path::new(report_file);
+            let chart = path.with_file_name(format!("{}_{}.svg", path.file_stem().unwrap_or_default().to_string_lossy(), gen.name));
+            report::chart(&chart.to_string_lossy(), &format!("{}, costs {}", gen.name, costs), &gen.sizes, &solvers, &cells);
+            md += &format!("\n![{}, costs {}]({})\n", gen.name, costs, chart.file_name().unwrap_or_default().to_string_lossy());
+        }
+        // after every generator, as in report.rs
+        fs::write(report_file, &md).unwrap_or_else(|e| panic!("{}: {}", report_file, e));
+    }
+    console::finish();
+    true
+}
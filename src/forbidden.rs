@@ -0,0 +1,87 @@
+use std::cmp;
+use crate::{BIG_VALUE, DSIZE, SSIZE};
+
+// Forbidden (supply, demand) pairs, kept beside the cost matrix as a bit set
+// so that no cost value has to double as a "you can't go there" marker
+pub struct Forbidden {
+    rows: usize,
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl Forbidden {
+    pub fn new(s_size: usize, d_size: usize) -> Forbidden {
+        Forbidden { rows: s_size, width: d_size, bits: vec![0; (s_size * d_size).div_ceil(64)] }
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|b| *b = 0);
+    }
+
+    pub fn forbid(&mut self, s: usize, d: usize) {
+        // a column past the width would set a bit of the next row
+        assert!(s < self.rows && d < self.width, "forbidden pair ({}, {}) outside of {}x{}", s, d, self.rows, self.width);
+        let idx = s * self.width + d;
+        self.bits[idx / 64] |= 1 << (idx % 64);
+    }
+
+    // cells outside of the model (padding of non-balanced models) are never forbidden
+    pub fn is_forbidden(&self, s: usize, d: usize) -> bool {
+        if d >= self.width {
+            return false;
+        }
+        let idx = s * self.width + d;
+        idx / 64 < self.bits.len() && self.bits[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+}
+
+// The cost solvers get instead of a forbidden cell. It is higher than any plan built of allowed
+// cells only, so a solver picks a forbidden cell only if there is no other way to assign the row.
+// Computed from the data, not a constant, so that it never collides with a real cost. With u16 costs
+// and at most SSIZE rows it stays below i32::MAX, but plans of many such cells need i64 sums.
+pub fn big_m(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) -> u32 {
+    let mut max_cost: u32 = 0;
+    for (s, row) in cost_arr.iter().enumerate().take(s_size) {
+        for (d, val) in row.iter().enumerate().take(d_size) {
+            if !forb.is_forbidden(s, d) && *val < BIG_VALUE {
+                max_cost = cmp::max(max_cost, *val as u32);
+            }
+        }
+    }
+    let big = (max_cost as u64).checked_mul(cmp::min(s_size, d_size) as u64).and_then(|m| m.checked_add(1)).expect("big M overflows");
+    debug_assert!(big <= i32::MAX as u64, "big M {} does not fit the i32 matrices of the adapters", big);
+    big as u32
+}
+
+// What a solver adapter should put into its input matrix for the given cell
+pub fn solver_cost(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, big_m: u32, s: usize, d: usize) -> u32 {
+    if forb.is_forbidden(s, d) {
+        big_m
+    } else {
+        cost_arr[s][d] as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Forbidden;
+
+    #[test]
+    fn forbid_stays_in_its_row() {
+        let mut forb = Forbidden::new(3, 5);
+        forb.forbid(1, 4);
+        assert!(forb.is_forbidden(1, 4));
+        assert!(!forb.is_forbidden(2, 0));
+        assert_eq!(forb.count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of 3x5")]
+    fn forbid_past_the_width() {
+        Forbidden::new(3, 5).forbid(1, 5);
+    }
+}
@@ -0,0 +1,254 @@
+use std::cmp;
+use crate::console::info;
+use crate::forbidden::Forbidden;
+use crate::{BIG_VALUE, DSIZE, SSIZE};
+
+// Greedy heuristics next to LCM, for sizes where exact solvers are too slow.
+// All return the cost and the (supply, demand) pairs in the order they were made;
+// forbidden pairs are never used, so a plan may come out smaller than min(cab_size, order_size).
+pub type Heuristic = fn(&[[u16; DSIZE]; SSIZE], &Forbidden, usize, usize) -> (u32, Vec<(usize, usize)>);
+
+// Row-wise nearest neighbour: every cab in turn takes the cheapest order still free
+pub fn nearest_neighbour(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> (u32, Vec<(usize, usize)>) {
+    let mut orders: Vec<bool> = vec![false; order_size];
+    let mut pairs: Vec<(usize, usize)> = vec![];
+    let mut sum_cost: u32 = 0;
+    for (cab, row) in cost_arr.iter().enumerate().take(cab_size) {
+        if pairs.len() == order_size {
+            break;
+        }
+        let mut best: Option<usize> = None;
+        for order in 0..order_size {
+            if !orders[order] && !forb.is_forbidden(cab, order) && best.is_none_or(|b| row[order] < row[b]) {
+                best = Some(order);
+            }
+        }
+        if let Some(order) = best {
+            orders[order] = true;
+            sum_cost += row[order] as u32;
+            pairs.push((cab, order));
+        }
+    }
+    (sum_cost, pairs)
+}
+
+// Max-regret greedy: the cab that would lose most by not getting its cheapest order
+// (second cheapest minus cheapest) is served first
+pub fn max_regret(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> (u32, Vec<(usize, usize)>) {
+    penalty_greedy(cost_arr, forb, cab_size, order_size, false)
+}
+
+// Vogel's approximation: like max-regret, but orders compete with their penalties too
+pub fn vogel(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> (u32, Vec<(usize, usize)>) {
+    penalty_greedy(cost_arr, forb, cab_size, order_size, true)
+}
+
+// Low Cost Method with all cells sorted once (counting sort, costs are small integers) instead of
+// a rescan of the remaining matrix for every pair: O(n^2 + values) against O(n^3).
+// Same plan as 'lcm', which takes the first minimum in row-major order: ties go to the lower cab,
+// then to the lower order, and the counting sort keeps that order within a cost.
+pub fn lcm_sorted(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> (u32, Vec<usize>) {
+    let cells = sorted_cells(cost_arr, cab_size, order_size);
+    let mut cabs: Vec<bool> = vec![false; cab_size];
+    let mut orders: Vec<bool> = vec![false; order_size];
+    let mut pairs: Vec<usize> = vec![];
+    let mut sum_cost: u32 = 0;
+    let how_many = cmp::min(cab_size, order_size);
+    for (cab, order) in cells {
+        let (cab, order) = (cab as usize, order as usize);
+        if cabs[cab] || orders[order] || forb.is_forbidden(cab, order) {
+            continue;
+        }
+        pairs.push(order);
+        sum_cost += cost_arr[cab][order] as u32;
+        cabs[cab] = true;
+        orders[order] = true;
+        if pairs.len() == how_many {
+            break;
+        }
+    }
+    if pairs.len() < how_many {
+        info("LCM only forbidden pairs left - no more interesting stuff here".to_string());
+    }
+    (sum_cost, pairs)
+}
+
+// All (cab, order) cells by cost, row-major within a cost; padding (BIG_VALUE) is left out, forbidden pairs are not
+pub fn sorted_cells(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize) -> Vec<(u32, u32)> {
+    let mut count: Vec<usize> = vec![0; BIG_VALUE as usize + 1];
+    for row in cost_arr.iter().take(cab_size) {
+        for val in row.iter().take(order_size) {
+            if *val < BIG_VALUE {
+                count[*val as usize + 1] += 1;
+            }
+        }
+    }
+    for v in 1..count.len() {
+        count[v] += count[v - 1]; // now where the cells of each cost start
+    }
+    let mut cells: Vec<(u32, u32)> = vec![(0, 0); count[BIG_VALUE as usize]];
+    for (cab, row) in cost_arr.iter().enumerate().take(cab_size) {
+        for (order, val) in row.iter().enumerate().take(order_size) {
+            if *val < BIG_VALUE {
+                cells[count[*val as usize]] = (cab as u32, order as u32);
+                count[*val as usize] += 1;
+            }
+        }
+    }
+    cells
+}
+
+// Only one allowed cell left: it has to be taken now, nothing can be higher
+const LAST_CHANCE: i32 = i32::MAX;
+
+// Two cheapest allowed cells of a line (row or column) among the lines of the other side still free
+#[derive(Clone, Copy)]
+struct Cheapest {
+    first: Option<usize>,
+    second: Option<usize>,
+}
+
+impl Cheapest {
+    fn penalty(&self, cost_of: impl Fn(usize) -> u16) -> Option<i32> {
+        match (self.first, self.second) {
+            (Some(f), Some(s)) => Some(cost_of(s) as i32 - cost_of(f) as i32),
+            (Some(_), None) => Some(LAST_CHANCE),
+            _ => None, // nothing left for this line
+        }
+    }
+}
+
+// Penalties are kept per line and recomputed only when one of its two cheapest cells is gone,
+// which keeps it about O(n^2) on random data instead of rescanning everything each round.
+// Ties go to the lower line index, rows before columns, and to the lower cell index within a line.
+fn penalty_greedy(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize,
+                  with_columns: bool) -> (u32, Vec<(usize, usize)>) {
+    let mut cabs: Vec<bool> = vec![false; cab_size];
+    let mut orders: Vec<bool> = vec![false; order_size];
+    let row_cheapest = |cab: usize, orders: &[bool]| -> Cheapest {
+        cheapest((0..order_size).filter(|o| !orders[*o] && !forb.is_forbidden(cab, *o)), |o| cost_arr[cab][o])
+    };
+    let col_cheapest = |order: usize, cabs: &[bool]| -> Cheapest {
+        cheapest((0..cab_size).filter(|c| !cabs[*c] && !forb.is_forbidden(*c, order)), |c| cost_arr[c][order])
+    };
+    let mut rows: Vec<Cheapest> = (0..cab_size).map(|cab| row_cheapest(cab, &orders)).collect();
+    let mut cols: Vec<Cheapest> = if with_columns {
+        (0..order_size).map(|order| col_cheapest(order, &cabs)).collect()
+    } else {
+        vec![]
+    };
+    let mut pairs: Vec<(usize, usize)> = vec![];
+    let mut sum_cost: u32 = 0;
+    for _ in 0..cmp::min(cab_size, order_size) {
+        let mut best: Option<(i32, usize, usize)> = None; // penalty, cab, order
+        for cab in (0..cab_size).filter(|c| !cabs[*c]) {
+            if let Some(p) = rows[cab].penalty(|o| cost_arr[cab][o]) {
+                if best.is_none_or(|(bp, _, _)| p > bp) {
+                    best = Some((p, cab, rows[cab].first.unwrap()));
+                }
+            }
+        }
+        for order in (0..cols.len()).filter(|o| !orders[*o]) {
+            if let Some(p) = cols[order].penalty(|c| cost_arr[c][order]) {
+                if best.is_none_or(|(bp, _, _)| p > bp) {
+                    best = Some((p, cols[order].first.unwrap(), order));
+                }
+            }
+        }
+        let Some((_, cab, order)) = best else {
+            info("Penalty greedy: only forbidden pairs left".to_string());
+            break;
+        };
+        cabs[cab] = true;
+        orders[order] = true;
+        sum_cost += cost_arr[cab][order] as u32;
+        pairs.push((cab, order));
+        // lines that have just lost one of their two cheapest cells
+        for c in 0..cab_size {
+            if !cabs[c] && (rows[c].first == Some(order) || rows[c].second == Some(order)) {
+                rows[c] = row_cheapest(c, &orders);
+            }
+        }
+        for o in 0..cols.len() {
+            if !orders[o] && (cols[o].first == Some(cab) || cols[o].second == Some(cab)) {
+                cols[o] = col_cheapest(o, &cabs);
+            }
+        }
+    }
+    (sum_cost, pairs)
+}
+
+fn cheapest(cells: impl Iterator<Item = usize>, cost_of: impl Fn(usize) -> u16) -> Cheapest {
+    let mut ret = Cheapest { first: None, second: None };
+    for i in cells {
+        let c = cost_of(i);
+        if ret.first.is_none_or(|f| c < cost_of(f)) {
+            ret.second = ret.first;
+            ret.first = Some(i);
+        } else if ret.second.is_none_or(|s| c < cost_of(s)) {
+            ret.second = Some(i);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{all_plan_costs, Graph};
+    use crate::{lcm, test_cost_arr, test_instance};
+    use super::{lcm_sorted, max_regret, nearest_neighbour, vogel, Heuristic};
+
+    // a plan of distinct allowed pairs at the cost it reports, that no free cab and free order could be added to,
+    // and never cheaper than the optimum of its size
+    #[test]
+    fn greedy_plans_are_valid() {
+        let heuristics: [(&str, Heuristic); 3] = [("nearest", nearest_neighbour), ("regret", max_regret), ("vogel", vogel)];
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(11);
+        for (s_size, d_size, forbidden_pct) in [(5, 5, 0), (4, 7, 0), (7, 4, 0), (6, 6, 30), (4, 6, 50), (6, 4, 50)] {
+            for max in [1, 3, 50] {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, max, forbidden_pct);
+                let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                let optimum = all_plan_costs(&graph)[0];
+                for (name, heuristic) in heuristics {
+                    let what = format!("{} on {}x{}, costs 0..={}, {}% forbidden", name, s_size, d_size, max, forbidden_pct);
+                    let (cost, pairs) = heuristic(&cost_arr, &forb, s_size, d_size);
+                    let mut cabs = vec![false; s_size];
+                    let mut orders = vec![false; d_size];
+                    for (s, d) in &pairs {
+                        assert!(!cabs[*s] && !orders[*d], "{}: ({}, {}) used twice", what, s, d);
+                        assert!(!forb.is_forbidden(*s, *d), "{}: forbidden pair ({}, {})", what, s, d);
+                        cabs[*s] = true;
+                        orders[*d] = true;
+                    }
+                    assert_eq!(cost, pairs.iter().map(|(s, d)| cost_arr[*s][*d] as u32).sum::<u32>(), "{}", what);
+                    for s in (0..s_size).filter(|s| !cabs[*s]) {
+                        assert!((0..d_size).all(|d| orders[d] || forb.is_forbidden(s, d)), "{}: cab {} left out", what, s);
+                    }
+                    if forbidden_pct == 0 {
+                        assert_eq!(pairs.len(), s_size.min(d_size), "{}", what);
+                        assert!(cost as u64 >= optimum, "{}: {} below the optimum {}", what, cost, optimum);
+                    }
+                }
+            }
+        }
+    }
+
+    // costs 0..=1 are mostly ties, so the order within a cost decides the plan
+    #[test]
+    fn lcm_sorted_gives_the_plan_of_lcm() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(12);
+        for (s_size, d_size) in [(1, 1), (6, 6), (4, 9), (9, 4), (30, 30), (20, 45)] {
+            for max in [1, 3, 1000] {
+                for forbidden_pct in [0, 30, 70] {
+                    let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, max, forbidden_pct);
+                    assert_eq!(lcm_sorted(&cost_arr, &forb, s_size, d_size), lcm(&cost_arr, &forb, s_size, d_size),
+                        "{}x{}, costs 0..={}, {}% forbidden", s_size, d_size, max, forbidden_pct);
+                }
+            }
+        }
+    }
+}
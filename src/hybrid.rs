@@ -0,0 +1,110 @@
+use std::cmp;
+use crate::forbidden::Forbidden;
+use crate::heuristics::sorted_cells;
+use crate::matching::{Assignment, Graph};
+use crate::{BIG_VALUE, DSIZE, SSIZE};
+
+// How much of the plan LCM may fix before the exact solver takes over
+#[derive(Clone, Copy, Debug)]
+pub enum HybridLimit {
+    Threshold(u16), // LCM pairs cheaper than this
+    Budget(usize),  // LCM pairs until the exact solver gets at most this many rows (or columns)
+}
+
+// Very large dispatch batches: the cheap, obvious pairs are fixed greedily in LCM order,
+// only the rest (cabs and orders not fixed) goes to the exact solver.
+// Returns the plan over the original indices and how many pairs LCM fixed.
+pub fn hybrid(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize,
+              limit: HybridLimit, exact: fn(&Graph) -> Assignment) -> (Assignment, usize) {
+    let mut row_to_col: Vec<Option<usize>> = vec![None; cab_size];
+    let mut col_to_row: Vec<Option<usize>> = vec![None; order_size];
+    let mut fixed: usize = 0;
+    for (cab, order) in sorted_cells(cost_arr, cab_size, order_size) {
+        let (cab, order) = (cab as usize, order as usize);
+        let stop = match limit {
+            HybridLimit::Threshold(threshold) => cost_arr[cab][order] >= threshold,
+            HybridLimit::Budget(budget) => cmp::min(cab_size, order_size) - fixed <= budget,
+        };
+        if stop {
+            break;
+        }
+        if row_to_col[cab].is_some() || col_to_row[order].is_some() || forb.is_forbidden(cab, order) {
+            continue;
+        }
+        row_to_col[cab] = Some(order);
+        col_to_row[order] = Some(cab);
+        fixed += 1;
+    }
+
+    // the rest, renumbered
+    let rows: Vec<usize> = (0..cab_size).filter(|s| row_to_col[*s].is_none()).collect();
+    let cols: Vec<usize> = (0..order_size).filter(|d| col_to_row[*d].is_none()).collect();
+    let adj: Vec<Vec<(usize, u32)>> = rows.iter().map(|s| {
+        cols.iter().enumerate()
+            .filter(|(_, d)| !forb.is_forbidden(*s, **d) && cost_arr[*s][**d] < BIG_VALUE)
+            .map(|(i, d)| (i, cost_arr[*s][*d] as u32)).collect()
+    }).collect();
+    let rest = exact(&Graph { rows: rows.len(), cols: cols.len(), adj });
+    for (i, j) in rest.row_to_col.iter().enumerate() {
+        if let Some(j) = j {
+            row_to_col[rows[i]] = Some(cols[*j]);
+            col_to_row[cols[*j]] = Some(rows[i]);
+        }
+    }
+    let cost: u64 = row_to_col.iter().enumerate()
+        .filter_map(|(s, d)| d.map(|d| cost_arr[s][d] as u64)).sum();
+    (Assignment { row_to_col, col_to_row, cost }, fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{min_cost_max_matching, Graph};
+    use crate::{test_cost_arr, test_instance};
+    use super::{hybrid, HybridLimit};
+
+    // a consistent plan of allowed pairs at the cost it reports; LCM keeps to its limit, and without it
+    // the plan is the exact one
+    #[test]
+    fn hybrid_plans_are_valid() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(13);
+        for (s_size, d_size, forbidden_pct) in [(8, 8, 0), (6, 10, 0), (10, 6, 0), (8, 8, 30)] {
+            for _ in 0..5 {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 30, forbidden_pct);
+                let exact = min_cost_max_matching(&Graph::from_matrix(&cost_arr, &forb, s_size, d_size));
+                let size = s_size.min(d_size);
+                for limit in [HybridLimit::Threshold(0), HybridLimit::Threshold(10), HybridLimit::Budget(size), HybridLimit::Budget(3),
+                              HybridLimit::Budget(0)] {
+                    let what = format!("{}x{}, {}% forbidden, {:?}", s_size, d_size, forbidden_pct, limit);
+                    let (plan, fixed) = hybrid(&cost_arr, &forb, s_size, d_size, limit, min_cost_max_matching);
+                    let mut cost: u64 = 0;
+                    for (s, d) in plan.row_to_col.iter().enumerate() {
+                        if let Some(d) = d {
+                            assert_eq!(plan.col_to_row[*d], Some(s), "{}", what);
+                            assert!(!forb.is_forbidden(s, *d), "{}: forbidden pair ({}, {})", what, s, d);
+                            cost += cost_arr[s][*d] as u64;
+                        }
+                    }
+                    assert_eq!(plan.cost, cost, "{}", what);
+                    assert!(plan.cost >= exact.cost || plan.size() < exact.size(), "{}: below the optimum {}", what, exact.cost);
+                    if fixed == 0 {
+                        assert_eq!((plan.size(), plan.cost), (exact.size(), exact.cost), "{}", what);
+                    }
+                    match limit {
+                        HybridLimit::Threshold(threshold) => assert!(threshold > 0 || fixed == 0, "{}: LCM fixed {}", what, fixed),
+                        // the exact solver gets what is left once LCM is down to the budget, more if forbidden pairs stop LCM early
+                        HybridLimit::Budget(budget) => assert!(size - fixed >= budget.min(size), "{}: LCM fixed {}", what, fixed),
+                    }
+                    if forbidden_pct == 0 {
+                        assert_eq!(plan.size(), size, "{}", what);
+                        if let HybridLimit::Budget(budget) = limit {
+                            assert_eq!(size - fixed, budget.min(size), "{}", what);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use crate::matching::{extend, Assignment, Graph};
+use crate::sap::Sap;
+
+// Dispatcher-style solver that lives across ticks: cabs (rows) and orders (columns) come, go
+// and change their costs, the matching and the potentials are kept and only the broken part
+// of the plan is re-optimised with a few augmentations.
+//
+// It works on the square extension of the instance (see 'extend'), so that the plan is always
+// a perfect matching and an update only has to keep reduced costs non-negative: a changed row
+// gets a new potential, a changed column too, and at most their pairs are broken up.
+// Ids of rows and columns are stable, removed ones stay as dead slots.
+// Edges of every row of 'ext' are kept sorted by column, updates look them up by binary search.
+pub struct Incremental {
+    ext: Graph,
+    sap: Sap,
+    big: u32,
+    row_ext: Vec<Option<usize>>, // supply id -> row of 'ext', None once removed
+    row_dummy: Vec<usize>,       // supply id -> its "unassigned" column
+    col_ext: Vec<Option<usize>>, // demand id -> column of 'ext', None once removed
+    col_dummy: Vec<usize>,       // demand id -> its "unassigned" row
+    ext_col_real: Vec<Option<usize>>, // column of 'ext' -> demand id, None for dummies
+    ext_col_supply: Vec<Option<usize>>, // column of 'ext' -> supply id it is "unassigned" for
+}
+
+impl Incremental {
+    // 'max_cost' bounds all costs that updates may bring, "unassigned" has to stay above any plan
+    pub fn new(graph: &Graph, max_cost: u32, max_pairs: usize) -> Incremental {
+        let big = max_cost * max_pairs as u32 + 1;
+        let mut ext = extend(graph, big);
+        for edges in ext.adj.iter_mut() {
+            edges.sort_unstable_by_key(|(d, _)| *d);
+        }
+        let mut sap = Sap::new(ext.rows, ext.cols);
+        sap.reduce_and_match(&ext, true, false);
+        sap.solve(&ext, ext.rows);
+        let mut ext_col_real: Vec<Option<usize>> = (0..graph.cols).map(Some).collect();
+        ext_col_real.resize(ext.cols, None);
+        let mut ext_col_supply: Vec<Option<usize>> = vec![None; graph.cols];
+        ext_col_supply.extend((0..graph.rows).map(Some));
+        Incremental {
+            sap,
+            big,
+            row_ext: (0..graph.rows).map(Some).collect(),
+            row_dummy: (0..graph.rows).map(|s| graph.cols + s).collect(),
+            col_ext: (0..graph.cols).map(Some).collect(),
+            col_dummy: (0..graph.cols).map(|d| graph.rows + d).collect(),
+            ext_col_real,
+            ext_col_supply,
+            ext,
+        }
+    }
+
+    // New costs of a row, forbidden pairs are missing; its pair is kept if it is still the best
+    pub fn update_row(&mut self, s: usize, edges: &[(usize, u32)]) {
+        let er = self.row_ext[s].expect("row was removed");
+        let old: Vec<usize> = self.real_cols(er);
+        let mut row: Vec<(usize, u32)> = edges.iter().map(|(d, c)| (self.col_ext[*d].expect("column was removed"), *c)).collect();
+        row.push((self.row_dummy[s], self.big));
+        row.sort_unstable_by_key(|(col, _)| *col);
+        self.ext.adj[er] = row;
+        // the dummy side follows the allowed pairs
+        let dummy_col = self.row_dummy[s];
+        let mut allowed: Vec<bool> = vec![false; self.col_ext.len()];
+        for (d, _) in edges {
+            allowed[*d] = true;
+        }
+        for d in &old {
+            if !allowed[*d] {
+                self.remove_edge(self.col_dummy[*d], dummy_col);
+            }
+            allowed[*d] = false; // already there
+        }
+        for (d, _) in edges {
+            if allowed[*d] {
+                self.add_edge(self.col_dummy[*d], dummy_col, 0);
+            }
+        }
+        self.reset_row(er);
+    }
+
+    // New costs of a column as (supply id, cost), forbidden pairs are missing
+    pub fn update_col(&mut self, d: usize, edges: &[(usize, u32)]) {
+        let ec = self.col_ext[d].expect("column was removed");
+        let dummy_row = self.col_dummy[d];
+        let mut allowed: Vec<bool> = vec![false; self.row_ext.len()];
+        for (s, _) in edges {
+            allowed[*s] = true;
+        }
+        for s in self.real_rows(d) {
+            if !allowed[s] {
+                self.remove_edge(self.row_ext[s].unwrap(), ec);
+                self.remove_edge(dummy_row, self.row_dummy[s]);
+            }
+        }
+        for (s, c) in edges {
+            let er = self.row_ext[*s].expect("row was removed");
+            if self.edge(er, ec).is_none() {
+                self.add_edge(dummy_row, self.row_dummy[*s], 0);
+            }
+            self.set_edge(er, ec, *c); // the column gets repriced below
+        }
+        self.reset_col(d);
+    }
+
+    pub fn add_row(&mut self, edges: &[(usize, u32)]) -> usize {
+        let s = self.row_ext.len();
+        let er = self.ext.rows;
+        let dummy_col = self.ext.cols;
+        let mut row: Vec<(usize, u32)> = edges.iter().map(|(d, c)| (self.col_ext[*d].expect("column was removed"), *c)).collect();
+        let pot = row.iter().map(|(d, c)| self.sap.col_pot[*d] - *c as i64).max().unwrap_or(0);
+        // low enough for the dummy rows of the allowed demand to get here
+        let dummy_pot = edges.iter().map(|(d, _)| self.sap.row_pot[self.col_dummy[*d]])
+            .fold(pot + self.big as i64, i64::min);
+        row.push((dummy_col, self.big));
+        row.sort_unstable_by_key(|(col, _)| *col);
+        self.ext.adj.push(row);
+        for (d, _) in edges {
+            self.ext.adj[self.col_dummy[*d]].push((dummy_col, 0));
+        }
+        self.ext.rows += 1;
+        self.ext.cols += 1;
+        self.ext_col_real.push(None);
+        self.ext_col_supply.push(Some(s));
+        self.sap.add_row(pot);
+        self.sap.add_col(dummy_pot);
+        self.row_ext.push(Some(er));
+        self.row_dummy.push(dummy_col);
+        s
+    }
+
+    pub fn add_col(&mut self, edges: &[(usize, u32)]) -> usize {
+        let d = self.col_ext.len();
+        let ec = self.ext.cols;
+        let dummy_row = self.ext.rows;
+        let pot = edges.iter().map(|(s, c)| *c as i64 + self.sap.row_pot[self.row_ext[*s].expect("row was removed")]).min().unwrap_or(0);
+        for (s, c) in edges {
+            self.ext.adj[self.row_ext[*s].unwrap()].push((ec, *c)); // the highest column so far
+        }
+        let mut row: Vec<(usize, u32)> = vec![(ec, 0)];
+        row.extend(edges.iter().map(|(s, _)| (self.row_dummy[*s], 0)));
+        row.sort_unstable_by_key(|(col, _)| *col);
+        let dummy_pot = row.iter().map(|(col, _)| if *col == ec { pot } else { self.sap.col_pot[*col] }).max().unwrap();
+        self.ext.adj.push(row);
+        self.ext.rows += 1;
+        self.ext.cols += 1;
+        self.ext_col_real.push(Some(d));
+        self.ext_col_supply.push(None);
+        self.sap.add_col(pot);
+        self.sap.add_row(dummy_pot);
+        self.col_ext.push(Some(ec));
+        self.col_dummy.push(dummy_row);
+        d
+    }
+
+    // The row stays as a dead slot paired with its own "unassigned" column
+    pub fn remove_row(&mut self, s: usize) {
+        let er = self.row_ext[s].take().expect("row was removed");
+        let dummy_col = self.row_dummy[s];
+        for d in self.real_cols(er) {
+            self.remove_edge(self.col_dummy[d], dummy_col);
+        }
+        self.ext.adj[er] = vec![(dummy_col, 0)];
+        self.reset_row(er);
+    }
+
+    // The column stays as a dead slot paired with its own "unassigned" row
+    pub fn remove_col(&mut self, d: usize) {
+        let ec = self.col_ext[d].take().expect("column was removed");
+        let dummy_row = self.col_dummy[d];
+        for s in self.real_rows(d) {
+            self.remove_edge(self.row_ext[s].unwrap(), ec);
+        }
+        self.ext.adj[dummy_row] = vec![(ec, 0)];
+        self.sap.unmatch_col(ec);
+        self.reset_row(dummy_row);
+    }
+
+    // Re-optimises after updates, returns the number of augmentations it took
+    pub fn optimise(&mut self) -> usize {
+        let free = self.sap.row_match.iter().filter(|d| d.is_none()).count();
+        self.sap.reprice_ends();
+        self.sap.solve(&self.ext, self.ext.rows);
+        free
+    }
+
+    pub fn assignment(&self) -> Assignment {
+        let mut row_to_col: Vec<Option<usize>> = vec![None; self.row_ext.len()];
+        let mut col_to_row: Vec<Option<usize>> = vec![None; self.col_ext.len()];
+        let mut cost: u64 = 0;
+        for (s, er) in self.row_ext.iter().enumerate() {
+            let Some(er) = er else { continue };
+            if let Some(d) = self.sap.row_match[*er].and_then(|ec| self.ext_col_real[ec]) {
+                row_to_col[s] = Some(d);
+                col_to_row[d] = Some(s);
+                cost += self.edge(*er, self.col_ext[d].unwrap()).unwrap() as u64;
+            }
+        }
+        Assignment { row_to_col, col_to_row, cost }
+    }
+
+    // The current instance by ids, for full re-solves; removed rows and columns have no pairs
+    pub fn real_graph(&self) -> Graph {
+        let adj: Vec<Vec<(usize, u32)>> = self.row_ext.iter().map(|er| match er {
+            Some(er) => self.ext.adj[*er].iter().filter_map(|(ec, c)| self.ext_col_real[*ec].map(|d| (d, *c))).collect(),
+            None => vec![],
+        }).collect();
+        Graph { rows: self.row_ext.len(), cols: self.col_ext.len(), adj }
+    }
+
+    fn real_cols(&self, er: usize) -> Vec<usize> {
+        self.ext.adj[er].iter().filter_map(|(ec, _)| self.ext_col_real[*ec]).collect()
+    }
+
+    // supply ids allowed for a demand, read off its "unassigned" row
+    fn real_rows(&self, d: usize) -> Vec<usize> {
+        let dummy_row = self.col_dummy[d];
+        self.ext.adj[dummy_row].iter().filter_map(|(ec, _)| self.ext_col_supply[*ec]).collect()
+    }
+
+    fn edge(&self, er: usize, ec: usize) -> Option<u32> {
+        let edges = &self.ext.adj[er];
+        edges.binary_search_by_key(&ec, |(col, _)| *col).ok().map(|i| edges[i].1)
+    }
+
+    fn set_edge(&mut self, er: usize, ec: usize, c: u32) {
+        let edges = &mut self.ext.adj[er];
+        match edges.binary_search_by_key(&ec, |(col, _)| *col) {
+            Ok(i) => edges[i].1 = c,
+            Err(i) => edges.insert(i, (ec, c)),
+        }
+    }
+
+    fn remove_edge(&mut self, er: usize, ec: usize) {
+        if let Ok(i) = self.ext.adj[er].binary_search_by_key(&ec, |(col, _)| *col) {
+            self.ext.adj[er].remove(i);
+        }
+        if self.sap.row_match[er] == Some(ec) {
+            self.sap.unmatch_row(er);
+        }
+    }
+
+    // a new edge must not have a negative reduced cost, otherwise its row is repriced
+    fn add_edge(&mut self, er: usize, ec: usize, c: u32) {
+        self.set_edge(er, ec, c);
+        if (c as i64) + self.sap.row_pot[er] - self.sap.col_pot[ec] < 0 {
+            self.reset_row(er);
+        }
+    }
+
+    // Lowest potential that keeps all edges of the row non-negative, the pair stays if it is tight
+    fn reset_row(&mut self, er: usize) {
+        let pot = self.ext.adj[er].iter().map(|(d, c)| self.sap.col_pot[*d] - *c as i64).max().unwrap();
+        if let Some(d) = self.sap.row_match[er] {
+            match self.edge(er, d) {
+                Some(c) if self.sap.col_pot[d] - c as i64 == pot => self.sap.pair(er, d, c),
+                _ => self.sap.unmatch_row(er),
+            }
+        }
+        self.sap.row_pot[er] = pot;
+    }
+
+    // Highest potential that keeps all edges into the column non-negative, the pair stays if it is tight
+    fn reset_col(&mut self, d: usize) {
+        let ec = self.col_ext[d].unwrap();
+        let mut rows: Vec<usize> = self.real_rows(d).iter().map(|s| self.row_ext[*s].unwrap()).collect();
+        rows.push(self.col_dummy[d]);
+        let mut pot = i64::MAX;
+        let mut tight: Option<(usize, u32)> = None;
+        for er in rows {
+            if let Some(c) = self.edge(er, ec) {
+                let p = c as i64 + self.sap.row_pot[er];
+                if p < pot {
+                    pot = p;
+                }
+                if self.sap.row_match[er] == Some(ec) {
+                    tight = Some((er, c));
+                }
+            }
+        }
+        match tight {
+            Some((er, c)) if c as i64 + self.sap.row_pot[er] == pot => self.sap.pair(er, ec, c),
+            _ => self.sap.unmatch_col(ec),
+        }
+        self.sap.col_pot[ec] = pot;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::matching::{all_plan_costs, hopcroft_karp, Graph};
+    use crate::{test_cost_arr, test_instance};
+    use super::Incremental;
+
+    const MAX: u16 = 20;
+    const MAX_PAIRS: usize = 16; // more than any plan of the tests
+
+    // allowed pairs of a changed or new row or column, about a third forbidden
+    fn random_edges(rng: &mut StdRng, ids: &[usize]) -> Vec<(usize, u32)> {
+        let mut edges = vec![];
+        for id in ids {
+            if rng.gen_ratio(2, 3) {
+                edges.push((*id, rng.gen_range(0..=MAX as u32)));
+            }
+        }
+        edges
+    }
+
+    // the plan after optimise against the cheapest of the largest plans of the instance as it is now
+    fn check(inc: &mut Incremental, what: &str) {
+        inc.optimise();
+        let plan = inc.assignment();
+        let graph = inc.real_graph();
+        assert_eq!(plan.size(), hopcroft_karp(&graph).0, "{}: not a largest plan", what);
+        assert_eq!(Some(plan.cost), all_plan_costs(&graph).first().copied(), "{}", what);
+    }
+
+    #[test]
+    fn each_update_matches_brute_force() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(8);
+        for update in ["update_row", "update_col", "add_row", "add_col", "remove_row", "remove_col"] {
+            for (s_size, d_size) in [(4, 4), (3, 5), (5, 3)] {
+                for _ in 0..10 {
+                    let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, MAX, 20);
+                    let mut inc = Incremental::new(&Graph::from_matrix(&cost_arr, &forb, s_size, d_size), MAX as u32, MAX_PAIRS);
+                    let (rows, cols): (Vec<usize>, Vec<usize>) = ((0..s_size).collect(), (0..d_size).collect());
+                    let s = rng.gen_range(0..s_size);
+                    let d = rng.gen_range(0..d_size);
+                    match update {
+                        "update_row" => inc.update_row(s, &random_edges(&mut rng, &cols)),
+                        "update_col" => inc.update_col(d, &random_edges(&mut rng, &rows)),
+                        "add_row" => { inc.add_row(&random_edges(&mut rng, &cols)); }
+                        "add_col" => { inc.add_col(&random_edges(&mut rng, &rows)); }
+                        "remove_row" => inc.remove_row(s),
+                        _ => inc.remove_col(d),
+                    }
+                    check(&mut inc, &format!("{} on {}x{}", update, s_size, d_size));
+                }
+            }
+        }
+    }
+
+    // ticks of the dispatcher: several updates, a row and a column leaving and coming, then one optimise
+    #[test]
+    fn ticks_match_brute_force() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(9);
+        let forb = test_instance(&mut cost_arr, &mut rng, 5, 5, MAX, 20);
+        let mut inc = Incremental::new(&Graph::from_matrix(&cost_arr, &forb, 5, 5), MAX as u32, MAX_PAIRS);
+        let mut rows: Vec<usize> = (0..5).collect();
+        let mut cols: Vec<usize> = (0..5).collect();
+        for tick in 0..30 {
+            for _ in 0..2 {
+                let s = rows[rng.gen_range(0..rows.len())];
+                inc.update_row(s, &random_edges(&mut rng, &cols));
+                let d = cols[rng.gen_range(0..cols.len())];
+                inc.update_col(d, &random_edges(&mut rng, &rows));
+            }
+            let idx = rng.gen_range(0..rows.len());
+            inc.remove_row(rows.swap_remove(idx));
+            let idx = rng.gen_range(0..cols.len());
+            inc.remove_col(cols.swap_remove(idx));
+            rows.push(inc.add_row(&random_edges(&mut rng, &cols)));
+            cols.push(inc.add_col(&random_edges(&mut rng, &rows)));
+            check(&mut inc, &format!("tick {}", tick));
+        }
+    }
+}
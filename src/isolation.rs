@@ -0,0 +1,181 @@
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use crate::console::note;
+use crate::forbidden::Forbidden;
+use crate::memory::{measured, run_child};
+use crate::results;
+use crate::timing::Timing;
+use crate::{read_instance, run_solver, Instance, Samples, Solvers, CHILD_CORES, DSIZE, HARNESS_CORES, INSTANCE_FILE, SSIZE};
+use strum::IntoEnumIterator;
+
+const RESULT_PREFIX: &str = "isolated result: "; // the line an isolated run hands back on stdout
+
+// Pins this process, and with it everything it starts, to HARNESS_CORES
+pub fn pin_harness() {
+    if HARNESS_CORES.is_empty() {
+        return;
+    }
+    if set_affinity(HARNESS_CORES) {
+        println!("Harness pinned to cores {:?}", HARNESS_CORES);
+    } else {
+        println!("Could not pin the harness to cores {:?} (Linux only)", HARNESS_CORES);
+    }
+}
+
+// Solver processes go to CHILD_CORES, set in the child between fork and exec
+pub fn pin_child(command: &mut Command) {
+    if CHILD_CORES.is_empty() {
+        return;
+    }
+    unsafe {
+        command.pre_exec(|| {
+            set_affinity(CHILD_CORES);
+            Ok(())
+        });
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cores: &[usize]) -> bool {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for core in cores {
+            libc::CPU_SET(*core, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_affinity(_cores: &[usize]) -> bool {
+    false
+}
+
+// Warns when the clock of the cores can change under a run: a governor other than 'performance',
+// or turbo boost, which depends on load and temperature
+pub fn check_frequency_scaling() {
+    if cfg!(target_os = "macos") {
+        println!("CPU frequency scaling: cannot be checked on macOS");
+        return;
+    }
+    let mut governors: Vec<(usize, String)> = vec![];
+    for entry in fs::read_dir("/sys/devices/system/cpu").into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(core) = name.strip_prefix("cpu").and_then(|n| n.parse::<usize>().ok()) else {
+            continue;
+        };
+        if let Ok(governor) = fs::read_to_string(entry.path().join("cpufreq/scaling_governor")) {
+            governors.push((core, governor.trim().to_string()));
+        }
+    }
+    if governors.is_empty() {
+        println!("CPU frequency scaling: no cpufreq in /sys, not checked");
+        return;
+    }
+    governors.sort();
+    let scaled: Vec<String> = governors.iter().filter(|(_, g)| g != "performance").map(|(c, g)| format!("{} ({})", c, g)).collect();
+    if !scaled.is_empty() {
+        println!("Warning: CPU frequency scaling is active on cores {}, use the performance governor for stable timings", scaled.join(", "));
+    }
+    let intel_turbo_on = fs::read_to_string("/sys/devices/system/cpu/intel_pstate/no_turbo").is_ok_and(|v| v.trim() == "0");
+    let boost = fs::read_to_string("/sys/devices/system/cpu/cpufreq/boost").is_ok_and(|v| v.trim() == "1");
+    if intel_turbo_on || boost {
+        println!("Warning: turbo boost is on, the clock depends on load and temperature");
+    }
+}
+
+// Which solvers run inside this process, the others start a process anyway
+pub fn in_process(key: &Solvers) -> bool {
+    !matches!(key, Solvers::PYTHON3 | Solvers::PYTHON5 | Solvers::CPP3 | Solvers::GLPK)
+}
+
+// One run of an in-crate solver in a fresh process of this program ("isolated" mode) on the instance
+// in INSTANCE_FILE, so that no heap or cache state is left over from the solver before. Its time, cost,
+// peak heap and Timing are put where an in-process run would have put them.
+pub fn run_isolated(key: &Solvers, munk_cost: u32, d_size: usize, s_size: usize, samples: &mut Samples) {
+    let name: &'static str = key.clone().into();
+    let exe = std::env::current_exe().expect("no path of the executable");
+    // the child loads the experiment file too, for the options of its solver
+    let file = crate::experiment::file().map_or(String::new(), |f| format!(" '{}'", f));
+    let Some(out) = run_child(&format!("'{}' isolated {} {} {} {}{}", exe.display(), name, munk_cost, d_size, s_size, file)) else {
+        return; // killed at its timeout
+    };
+    let mut result: Option<Outcome> = None;
+    for line in out.lines() {
+        match line.strip_prefix(RESULT_PREFIX) {
+            Some(fields) => result = decode(fields),
+            None => note(line.to_string()), // what the run had to say about its plan, at its verbosity
+        }
+    }
+    let Some(outcome) = result else {
+        note(format!("{}: the isolated run gave no result", name));
+        return;
+    };
+    let idx = key.clone() as usize;
+    samples.time[idx].push(outcome.time);
+    if let Some(c) = outcome.cost {
+        samples.cost[idx].push(c);
+    }
+    samples.mem[idx].push(outcome.mem);
+    samples.timing[idx].push(outcome.timing);
+    results::set_flags(outcome.flags);
+}
+
+// what an isolated run hands back
+struct Outcome {
+    time: u128,
+    cost: Option<u32>,
+    mem: u64,
+    flags: (bool, bool), // see results.rs
+    timing: Timing,
+}
+
+// time cost mem flags, then the Timing
+fn decode(fields: &str) -> Option<Outcome> {
+    let mut parts = fields.splitn(5, ' ');
+    let time = parts.next()?.parse().ok()?;
+    let cost = match parts.next()? {
+        "-" => None,
+        c => Some(c.parse().ok()?),
+    };
+    let mem = parts.next()?.parse().ok()?;
+    let flags = parts.next()?;
+    Some(Outcome { time, cost, mem, flags: (flags.contains('i'), flags.contains('s')), timing: Timing::decode(parts.next()?)? })
+}
+
+// The child side: "isolated KEY EXPECTED_COST DEMAND_SIZE SUPPLY_SIZE [EXPERIMENT_FILE]"
+pub fn isolated_child(args: &[String], cost_arr: &mut [[u16; DSIZE]; SSIZE]) {
+    let Some((key, munk_cost, d_size, s_size)) = child_args(args) else {
+        println!("Usage: isolated SOLVER EXPECTED_COST DEMAND_SIZE SUPPLY_SIZE [EXPERIMENT_FILE]");
+        return;
+    };
+    if let Some(file) = args.get(6) {
+        if !crate::experiment::apply_file(file) {
+            println!("{}: not a valid experiment file", file);
+            return;
+        }
+    }
+    let forb: Forbidden = read_instance(INSTANCE_FILE, cost_arr, s_size, d_size);
+    let mut samples = Samples::new();
+    measured(key.clone(), &mut samples.mem, &mut samples.timing,
+        || run_solver(&key, munk_cost, &Instance { cost_arr, forb: &forb, s_size, d_size }, &mut samples.cost, &mut samples.time));
+    let idx = key as usize;
+    let (Some(time), Some(mem), Some(timing)) = (samples.time[idx].last(), samples.mem[idx].last(), samples.timing[idx].last()) else {
+        return;
+    };
+    let flags = match results::take_flags() {
+        (false, false) => "-",
+        (true, false) => "i",
+        (false, true) => "s",
+        (true, true) => "is",
+    };
+    println!("{}{} {} {} {} {}", RESULT_PREFIX, time, samples.cost[idx].last().map_or("-".to_string(), |c| c.to_string()), mem, flags,
+        timing.encode());
+}
+
+fn child_args(args: &[String]) -> Option<(Solvers, u32, usize, usize)> {
+    let name = args.get(2)?;
+    let key = Solvers::iter().find(|s| <&'static str>::from(s.clone()) == name.as_str())?;
+    Some((key, args.get(3)?.parse().ok()?, args.get(4)?.parse().ok()?, args.get(5)?.parse().ok()?))
+}
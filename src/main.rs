@@ -4,17 +4,55 @@ use std::hash::Hash;
 use std::fs::{File, remove_file};
 use std::io::{BufRead, BufReader, Write};
 use std::process::Command;
-use std::time::Instant;
-use chrono::Utc;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
 use ndarray::{Array, ArrayBase, OwnedRepr};
 use pathfinding::num_traits::float;
 use rand::Rng;
+use rand::seq::SliceRandom;
 use hungarian::minimize;
 use pathfinding::prelude::{kuhn_munkres_min, Matrix};
 use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
 use lapjv::lapjv;
 use ndarray::Array2;
 
+mod auction;
+mod bench;
+mod bottleneck;
+mod candidates;
+mod components;
+mod console;
+mod environment;
+mod experiment;
+mod forbidden;
+mod heuristics;
+mod hybrid;
+mod incremental;
+mod isolation;
+mod matching;
+mod memory;
+mod murty;
+mod network_simplex;
+mod report;
+mod results;
+mod sap;
+mod stats;
+mod timing;
+mod transport;
+use auction::auction;
+use bottleneck::{bottleneck, bottleneck_min_sum};
+use console::{info, note, Verbosity};
+use forbidden::{big_m, solver_cost, Forbidden};
+use heuristics::{lcm_sorted, max_regret, nearest_neighbour, vogel, Heuristic};
+use hybrid::{hybrid, HybridLimit};
+use memory::{measured, megabytes, run_child};
+use matching::{min_cost_max_matching, Assignment, Graph};
+use murty::k_best;
+use network_simplex::ns_assignment;
+use results::{invalid, suboptimal};
+use timing::{phase, self_reported, Timing};
+
 /*
 https://discuss.python.org/t/on-macos-14-pip-install-throws-error-externally-managed-environment/50352/3
 mkdir ~/.venv
@@ -67,149 +105,566 @@ enum Solvers {
     PYTHON5,
     CPP4,
     RUST3,
-    LCM
+    LCM,
+    #[strum(serialize = "MAXCARD")]
+    MaxCard,
+    #[strum(serialize = "MURTY")]
+    Murty,
+    #[strum(serialize = "VOGEL")]
+    Vogel,
+    #[strum(serialize = "REGRET")]
+    Regret,
+    #[strum(serialize = "NEAREST")]
+    Nearest,
+    LCM2,
+    #[strum(serialize = "HYBRID")]
+    Hybrid,
+    #[strum(serialize = "BOTTLENECK")]
+    Bottleneck,
+    #[strum(serialize = "BOTTLESUM")]
+    BottleSum,
+    #[strum(serialize = "NETSIMPLEX")]
+    NetSimplex,
+    #[strum(serialize = "AUCTION")]
+    Auction
 }
 
-const BIG_VALUE: u16 = 65255;
+const BIG_VALUE: u16 = 65255; // padding of non-balanced models, forbidden pairs are kept in 'Forbidden'
 const MIN_VALUE: u16 = 0;
 const MAX_VALUE: u16 = 30;
+const FORBIDDEN_PCT: u32 = 0; // how many percent of cells are forbidden pairs
+const COSTS: CostRange = CostRange { min: MIN_VALUE, max: MAX_VALUE, zero_pct: 0, forbidden_pct: FORBIDDEN_PCT }; // of the main loop, e.g. zero_pct 50 with min 1
 const MAX_ITER: usize = 5;
+const WARMUP_ITER: usize = 1; // iterations run first and not counted: caches, page faults, CPU clock
+const REPEATS: usize = 3; // timed runs of every solver on the same instance
+const ADAPTIVE_REPEATS: (usize, usize) = (8, 200); // adaptive mode (see bench.rs): runs of a solver per instance, (minimum, maximum)
+const SLOW_REPEATS: (usize, usize) = (8, 20); // the same for SLOW_SOLVERS
+const SLOW_SOLVERS: [Solvers; 4] = [Solvers::PYTHON3, Solvers::PYTHON5, Solvers::CPP3, Solvers::Murty]; // a process per run, or K_BEST solves
+const TIME_BUDGET: Duration = Duration::from_secs(1800); // for the whole adaptive run; once spent, solvers stop at their minimum
+const HARNESS_CORES: &[usize] = &[]; // cores this process is pinned to (Linux), empty: not pinned
+const CHILD_CORES: &[usize] = &[]; // cores of the solver processes, e.g. &[3] with HARNESS_CORES &[2]
+const ISOLATED: bool = false; // in-crate solvers get a process of their own too, see isolation.rs
+const INSTANCE_FILE: &str = "instance.txt"; // the instance for isolated runs
+const RESULTS_FILE: &str = "results.csv"; // every timed run, see results.rs
+const VERBOSITY: Verbosity = Verbosity::Normal; // what is printed during the runs, see console.rs
+const MIN_COMPARED: usize = 6; // runs a solver needs to be in the significance matrix
+const K_BEST: usize = 5; // how many plans Murty's algorithm should list
+const HYBRID_LIMIT: HybridLimit = HybridLimit::Budget(500); // what LCM may fix before the exact solver
+const GLPK_MAX_SIZE: usize = 100; // above that glpk.py runs out of memory
+const SOLVERS: usize = 32; // slots in cost_vec and time_vec, one per 'Solvers'
 const DSIZE: usize = 32001;
 const SSIZE: usize = 32001;
+// the solvers compared by the main loop, see run_solver
+const COMPARED: [Solvers; 16] = [Solvers::RUST2, Solvers::GLPK, Solvers::NetSimplex, Solvers::MaxCard, Solvers::Bottleneck, Solvers::BottleSum,
+    Solvers::Auction, Solvers::Murty, Solvers::PYTHON3, Solvers::PYTHON5, Solvers::CPP3, Solvers::LCM2, Solvers::Hybrid,
+    Solvers::Vogel, Solvers::Regret, Solvers::Nearest];
+// How the costs of an instance are drawn: zero_pct percent of the cells cost 0 (a sparse matrix),
+// the others are uniform in min..max; forbidden_pct percent of the cells are forbidden pairs
+#[derive(Clone, Copy, Debug)]
+struct CostRange {
+    min: u16,
+    max: u16,
+    zero_pct: u32,
+    forbidden_pct: u32,
+}
+
+impl std::fmt::Display for CostRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.zero_pct > 0 {
+            write!(f, "{}% 0, {}..{}", self.zero_pct, self.min, self.max)
+        } else {
+            write!(f, "{}..{}", self.min, self.max)
+        }
+    }
+}
+
 static mut cost: [[u16; DSIZE]; SSIZE] = [[0; DSIZE]; SSIZE];
 
+// A zeroed cost matrix for the tests, on the heap: it is far too large for the stack of a test thread
+#[cfg(test)]
+fn test_cost_arr() -> Box<[[u16; DSIZE]; SSIZE]> {
+    let layout = std::alloc::Layout::new::<[[u16; DSIZE]; SSIZE]>();
+    // all zeros is a valid matrix, and the pages only get mapped where a test writes
+    let ptr = unsafe { std::alloc::alloc_zeroed(layout) } as *mut [[u16; DSIZE]; SSIZE];
+    if ptr.is_null() {
+        std::alloc::handle_alloc_error(layout);
+    }
+    unsafe { Box::from_raw(ptr) }
+}
+
+// A small random instance for the tests: costs in 0..=max, about 'forbidden_pct' percent of the pairs forbidden
+#[cfg(test)]
+fn test_instance(cost_arr: &mut [[u16; DSIZE]; SSIZE], rng: &mut impl Rng, s_size: usize, d_size: usize, max: u16,
+                 forbidden_pct: u32) -> Forbidden {
+    let mut forb = Forbidden::new(s_size, d_size);
+    for (s, row) in cost_arr.iter_mut().enumerate().take(s_size) {
+        for (d, val) in row.iter_mut().enumerate().take(d_size) {
+            *val = rng.gen_range(0..=max);
+            if rng.gen_range(0..100) < forbidden_pct {
+                forb.forbid(s, d);
+            }
+        }
+    }
+    forb
+}
+
+// The instance a solver runs on: the cost matrix and forbidden pairs, supply x demand
+#[derive(Clone, Copy)]
+struct Instance<'a> {
+    cost_arr: &'a [[u16; DSIZE]; SSIZE],
+    forb: &'a Forbidden,
+    s_size: usize,
+    d_size: usize,
+}
+
+// What the timed runs gave, a vec per solver indexed by 'Solvers'
+struct Samples {
+    cost: [Vec<u32>; SOLVERS],
+    time: [Vec<u128>; SOLVERS],
+    mem: [Vec<u64>; SOLVERS], // peak bytes, see memory.rs
+    timing: [Vec<Timing>; SOLVERS], // CPU time and phases, see timing.rs
+}
+
+impl Samples {
+    fn new() -> Samples {
+        Samples { cost: [const { Vec::new() }; SOLVERS], time: [const { Vec::new() }; SOLVERS], mem: [const { Vec::new() }; SOLVERS],
+            timing: [const { Vec::new() }; SOLVERS] }
+    }
+
+    // after the warm-up runs, which are not counted
+    fn clear(&mut self) {
+        self.cost.iter_mut().for_each(Vec::clear);
+        self.time.iter_mut().for_each(Vec::clear);
+        self.mem.iter_mut().for_each(Vec::clear);
+        self.timing.iter_mut().for_each(Vec::clear);
+    }
+}
+
 fn main()  -> std::io::Result<()> {
     unsafe {
     let demand_size: usize = 2000;
     let supply_size: usize = 2000;
     let max_size: usize = cmp::max(demand_size, supply_size);
-    let mut cost_vec: [Vec<u32>; 20] = [const { Vec::new() }; 20];
-    let mut time_vec: [Vec<u128>; 20] = [const { Vec::new() }; 20];
+    let mut samples = Samples::new();
+
+    let mut forb = Forbidden::new(max_size, max_size);
+
+    // a single solver run for the main loop, see isolation.rs; before init_cost as that fills all of 'cost'
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|a| a.as_str()) == Some("isolated") {
+        isolation::isolated_child(&args, &mut cost);
+        return Ok(());
+    }
+    // "regress BASELINE [RESULTS]": exits with 1 if RESULTS (results.csv) got slower, invalid or suboptimal or lost
+    // a solver or class of the baseline, with 2 if the files cannot be compared or BASELINE is missing
+    if args.get(1).map(|a| a.as_str()) == Some("regress") {
+        let Some(baseline) = args.get(2) else {
+            println!("Usage: regress BASELINE [RESULTS]");
+            std::process::exit(2);
+        };
+        match results::regress(baseline, args.get(3).map_or(RESULTS_FILE, |r| r.as_str())) {
+            Some(false) => {}
+            Some(true) => std::process::exit(1),
+            None => std::process::exit(2),
+        }
+        return Ok(());
+    }
 
     init_cost(&mut cost, max_size);
+    print!("{}", environment::describe());
+    isolation::check_frequency_scaling();
+    isolation::pin_harness();
+
+    // other modes than comparing solvers, e.g. "cargo run --release -- incremental"
+    match std::env::args().nth(1).as_deref() {
+        Some("incremental") => {
+            random_cost(&mut cost, &mut forb, supply_size, demand_size);
+            bench::bench_incremental(&cost, &forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some("candidates") => {
+            random_cost(&mut cost, &mut forb, supply_size, demand_size);
+            bench::bench_candidates(&cost, &forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some("components") => {
+            random_cost(&mut cost, &mut forb, supply_size, demand_size);
+            bench::bench_components(&cost, &mut forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some("threads") => {
+            random_cost(&mut cost, &mut forb, supply_size, demand_size);
+            bench::bench_threads(&cost, &forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some("hybrid") => {
+            random_cost(&mut cost, &mut forb, supply_size, demand_size);
+            bench::bench_hybrid(&cost, &forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some("lcm") => {
+            bench::bench_lcm(&mut cost, &mut forb);
+            return Ok(());
+        }
+        Some("transport") => {
+            bench::bench_transport(&mut cost, &mut forb);
+            return Ok(());
+        }
+        Some("adaptive") => {
+            bench::bench_adaptive(&mut cost, &mut forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some("report") => {
+            report::report(&mut cost);
+            return Ok(());
+        }
+        // "experiment FILE", see experiment.rs and experiments/
+        Some("experiment") => {
+            let Some(file) = args.get(2) else {
+                println!("Usage: experiment FILE");
+                return Ok(());
+            };
+            if !experiment::run(file, &mut cost) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some("warmstart") => {
+            random_cost(&mut cost, &mut forb, supply_size, demand_size);
+            bench::bench_warm_start(&mut cost, &forb, supply_size, demand_size);
+            return Ok(());
+        }
+        Some(mode) => {
+            println!("Unknown mode: {}", mode);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // run in a new random order in every round so that no solver always comes right after
+    // the instance was generated or after a particular other solver
+    let mut order: Vec<Solvers> = COMPARED.to_vec();
+    let mut rng = rand::thread_rng();
+    results::open(RESULTS_FILE, instance_class(supply_size, demand_size, &COSTS));
+    console::start((WARMUP_ITER + MAX_ITER) * REPEATS * COMPARED.len());
 
-    for iter in 0 .. MAX_ITER {
-        println!("Iter {} start: {:?}", iter, Utc::now());
-        unsafe { random_cost(&mut cost, supply_size, demand_size); }
-        
+    for iter in 0 .. WARMUP_ITER + MAX_ITER {
+        if iter < WARMUP_ITER {
+            console::stage(format!("Iter {} (warm-up)", iter));
+        } else {
+            console::stage(format!("Iter {} of {}", iter - WARMUP_ITER, MAX_ITER));
+        }
+        results::set_instance(iter.checked_sub(WARMUP_ITER));
+        if iter == WARMUP_ITER {
+            samples.clear();
+        }
         // ----------------- RUST ----------------------
         // https://crates.io/crates/hungarian
-        //let munk_cost = run_munkres(demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        //let munk_cost = run_munkres(demand_size, supply_size, &cost, &forb, &mut cost_vec, &mut time_vec);
 
-        // --------------- GLPK ------------------
-        //run("python3 glpk.py", Solvers::GLPK, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        let munk_cost = new_instance(&mut cost, &mut forb, supply_size, demand_size, &COSTS, &mut rng);
+
+        for _ in 0..REPEATS {
+            order.shuffle(&mut rng);
+            for key in order.iter() {
+                run_timed(key, munk_cost, demand_size, supply_size, &cost, &forb, &mut samples);
+            }
+        }
+    }
+    console::finish();
+    
+    print_summary(&samples, &COMPARED, true);
+    Ok(())
+}
+}
 
+// A new random instance: the expected cost comes from a reference solve outside the timed runs
+fn new_instance(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &mut Forbidden, s_size: usize, d_size: usize, costs: &CostRange,
+                rng: &mut impl Rng) -> u32 {
+    random_cost_in(cost_arr, forb, s_size, d_size, costs, rng);
+    let forbidden = forb.count();
+    if forbidden > 0 {
+        info(format!("Forbidden pairs: {}", forbidden));
+    }
+    let munk_cost = reference_cost(cmp::max(d_size, s_size), cost_arr, forb);
+    if experiment::isolated() {
+        write_instance(INSTANCE_FILE, s_size, d_size, cost_arr, forb);
+    }
+    munk_cost
+}
+
+// A run of run_solver with its memory and timings, in this process or (ISOLATED) in one of its own.
+// Returns whether the plan was (invalid, suboptimal); a run killed at its timeout counts as invalid.
+fn run_timed(key: &Solvers, munk_cost: u32, demand_size: usize, supply_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+             samples: &mut Samples) -> (bool, bool) {
+    let idx = key.clone() as usize;
+    let (costs, times) = (samples.cost[idx].len(), samples.time[idx].len());
+    console::run(key);
+    let timeout = experiment::timeout(key);
+    memory::set_timeout(timeout);
+    if experiment::isolated() && isolation::in_process(key) {
+        isolation::run_isolated(key, munk_cost, demand_size, supply_size, samples);
+    } else {
+        measured(key.clone(), &mut samples.mem, &mut samples.timing,
+            || run_solver(key, munk_cost, &Instance { cost_arr, forb, s_size: supply_size, d_size: demand_size }, &mut samples.cost,
+                          &mut samples.time));
+    }
+    let mut flags = results::take_flags();
+    if samples.time[idx].len() > times {
+        results::record(key, samples.time[idx][times], samples.cost[idx].get(costs).copied(), munk_cost, flags);
+    } else if let Some(limit) = timeout.filter(|_| memory::timed_out()) {
+        // a killed run counts at its timeout, flagged as invalid
+        flags.0 = true;
+        samples.time[idx].push(limit.as_millis());
+        results::record(key, limit.as_millis(), None, munk_cost, flags);
+    }
+    console::done();
+    flags
+}
+
+// what the instances are drawn from, runs of one class are compared by regress
+fn instance_class(s_size: usize, d_size: usize, costs: &CostRange) -> String {
+    format!("{}x{} {} {}% forbidden", s_size, d_size, costs, costs.forbidden_pct)
+}
+
+// One timed run of a solver of COMPARED on the current instance
+fn run_solver(key: &Solvers, munk_cost: u32, inst: &Instance, cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let Instance { cost_arr, forb, s_size: supply_size, d_size: demand_size } = *inst;
+    let max_size: usize = cmp::max(demand_size, supply_size);
+    match key {
         // ------------------ RUST faster ------------------
         // https://crates.io/crates/pathfinding/4.3.1
         // !! "number of rows must not be larger than number of columns"
         // then 500*8000 needs 8000x8000
-        let munk_cost = run_munkres2(0, max_size, max_size, &cost, &mut cost_vec, &mut time_vec);
+        Solvers::RUST2 => { run_munkres2(munk_cost, max_size, max_size, cost_arr, forb, cost_vec, time_vec); }
+
+        // --------------- GLPK ------------------
+        // dense 2n x n^2 constraint matrix, small n only; larger instances or no GLPK: not run, see NETSIMPLEX
+        Solvers::GLPK if glpk_available() && max_size <= experiment::glpk_max_size() =>
+            run("python3 glpk.py", Solvers::GLPK, munk_cost, inst, cost_vec, time_vec),
+        Solvers::GLPK => info(format!("GLPK skipped, {}", if glpk_available() { "instance above its max_size" } else { "cvxopt.glpk not found" })),
+
+        // the LP reference that needs no GLPK, any size
+        Solvers::NetSimplex => run_network_simplex(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
+
+        // maximum-cardinality plan, rows and columns that cannot be covered are reported
+        // should give the same cost as Munkres2 as big M makes it use as few forbidden pairs as possible
+        Solvers::MaxCard => run_maxcard(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
+
+        // the longest pickup as short as possible, then (BOTTLESUM) the cheapest plan keeping to it
+        Solvers::Bottleneck => run_bottleneck(Solvers::Bottleneck, bottleneck, munk_cost, inst, cost_vec, time_vec),
+        Solvers::BottleSum => run_bottleneck(Solvers::BottleSum, bottleneck_min_sum, munk_cost, inst, cost_vec, time_vec),
+
+        // parallel auction on all cores, see "cargo run --release -- threads" for the scaling
+        Solvers::Auction => run_auction(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
+
+        // k best plans, fallbacks when a driver declines
+        Solvers::Murty => run_murty(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
 
         // https://crates.io/crates/lapjv/0.2.1
         // "matrix is not square"
-        //run_lapjv(munk_cost, max_size, max_size, &cost, &mut cost_vec, &mut time_vec);
+        //Solvers::RUST3 => run_lapjv(munk_cost, max_size, max_size, cost_arr, forb, cost_vec, time_vec),
 
         // ---------------- C ------------------------
         // https://github.com/xg590/munkres
         // fails e.g. with n=1000
         // Segm fault e.g. with n=1000
-        //run("./munkres1", Solvers::C, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        //Solvers::C => run("./munkres1", Solvers::C, munk_cost, inst, cost_vec, time_vec),
 
         // ---------------- C++ ------------------------
         // https://github.com/mcximing/hungarian-algorithm-cpp
         // 1000x2000, 30..1800: plan is invalid
         // 500x8000, 0..30: duplicates found
-        //run("./munkres2", Solvers::CPP, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        //Solvers::CPP => run("./munkres2", Solvers::CPP, munk_cost, inst, cost_vec, time_vec),
 
         // ---------------- C++ ------------------------
         // https://github.com/phoemur/hungarian_algorithm/blob/master/hungarian.cpp
         // SLOW, 500x8000: very slow
-        //run("./munkres3", Solvers::CPP2, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
-        
+        //Solvers::CPP2 => run("./munkres3", Solvers::CPP2, munk_cost, inst, cost_vec, time_vec),
+
         // ---------------- Python
         // https://software.clapper.org/munkres/
         // python3 -m pip install munkres
         // SLOW
-        //generate_python("munk.py", supply_size, demand_size, &cost);
-        //run("python3 munk.py", Solvers::PYTHON, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec); 
+        //generate_python("munk.py", supply_size, demand_size, &cost, &forb);
+        //Solvers::PYTHON => run("python3 munk.py", Solvers::PYTHON, munk_cost, inst, cost_vec, time_vec),
 
         // ---------------- Python LAPJV
         // https://github.com/src-d/lapjv
         // python3 -m pip install lapjv
         // 1000x2000: ValueError: "cost_matrix" must be a square 2D numpy array, 
         // x8000: "Killed"
-        //generate_python2("munk2.py", max_size, max_size, &cost);
-        //run("python3 munk2.py", Solvers::PYTHON2, munk_cost, max_size, max_size, &cost, &mut cost_vec, &mut time_vec);
-        
+        //generate_python2("munk2.py", max_size, max_size, &cost, &forb);
+        //Solvers::PYTHON2 => run("python3 munk2.py", Solvers::PYTHON2, munk_cost, &Instance { s_size: max_size, d_size: max_size, ..*inst }, cost_vec, time_vec),
+
         // https://docs.scipy.org/doc/scipy/reference/generated/scipy.optimize.linear_sum_assignment.html
-        generate_python3("munk3.py", supply_size, demand_size, &cost);
-        run("python3 munk3.py", Solvers::PYTHON3, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
-        
+        Solvers::PYTHON3 => run("python3 munk3.py", Solvers::PYTHON3, munk_cost, inst, cost_vec, time_vec),
+
         // https://github.com/cheind/py-lapsolver
         // even the GitHub readme "usage" example fails
-        //generate_python4("munk4.py", supply_size, demand_size, &cost);
-        //run("python3 munk4.py", Solvers::PYTHON4, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        //generate_python4("munk4.py", supply_size, demand_size, &cost, &forb);
+        //Solvers::PYTHON4 => run("python3 munk4.py", Solvers::PYTHON4, munk_cost, inst, cost_vec, time_vec),
 
         // https://github.com/jdmoorman/laptools
-        generate_python5("munk5.py", supply_size, demand_size, &cost);
-        run("python3 munk5.py", Solvers::PYTHON5, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
-        
+        Solvers::PYTHON5 => run("python3 munk5.py", Solvers::PYTHON5, munk_cost, inst, cost_vec, time_vec),
+
 
         // ---------- C ----
         // https://ranger.uta.edu/~weems/NOTES5311/hungarian.c
         // hangs when non-balanced, at least 1000x2000, 30..1800
         // very slow in x8000
         // ..18000 (denser): Segm fault
-        //run("./munkres4", Solvers::C2, munk_cost, max_size, max_size, &cost, &mut cost_vec, &mut time_vec);
+        //Solvers::C2 => run("./munkres4", Solvers::C2, munk_cost, &Instance { s_size: max_size, d_size: max_size, ..*inst }, cost_vec, time_vec),
         // !! no use to read as it hang when non-balance
 
         // ---------------- C++ -----------------
         // https://github.com/yongyanghz/LAPJV-algorithm-c
         // this implementation assumes quadratic cost matrix, balanced models
-        run("./lap1", Solvers::CPP3, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
-     
+        Solvers::CPP3 => run("./lap1", Solvers::CPP3, munk_cost, inst, cost_vec, time_vec),
+
         // https://github.com/aaron-michaux/munkres-algorithm.git
         // does not compile on Mac
         // SLOW on Ubuntu
         // 1000x2000, 30..1800: non-optimal value + slow
-        //run("./munkres6", Solvers::CPP4, munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        //Solvers::CPP4 => run("./munkres6", Solvers::CPP4, munk_cost, inst, cost_vec, time_vec),
 
         // Low Cost Method, just for comparison
-        //run_lcm(munk_cost, demand_size, supply_size, &cost, &mut cost_vec, &mut time_vec);
+        //Solvers::LCM => run_lcm(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
+        // the same plan with cells sorted once, see "cargo run --release -- lcm"
+        Solvers::LCM2 => run_lcm2(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
+
+        // LCM fixes the cheap pairs, the exact solver does the rest
+        Solvers::Hybrid => run_hybrid(munk_cost, demand_size, supply_size, cost_arr, forb, cost_vec, time_vec),
+
+        // other greedy heuristics, how much faster and how much worse than exact
+        Solvers::Vogel => run_heuristic(Solvers::Vogel, vogel, munk_cost, inst, cost_vec, time_vec),
+        Solvers::Regret => run_heuristic(Solvers::Regret, max_regret, munk_cost, inst, cost_vec, time_vec),
+        Solvers::Nearest => run_heuristic(Solvers::Nearest, nearest_neighbour, munk_cost, inst, cost_vec, time_vec),
+        _ => {}
     }
-    
-    for solv in Solvers::iter() {
+}
+
+// A line per solver that was meant to run; 'paired': the i-th runs of all solvers were on the same instance
+// in the same round, see stats::compare
+fn print_summary(samples: &Samples, solvers: &[Solvers], paired: bool) {
+    let Samples { cost: cost_vec, time: time_vec, mem: mem_vec, timing: timing_vec } = samples;
+    for solv in solvers.iter().cloned() {
         let name: &'static str = solv.clone().into();
         if time_vec[solv.clone() as usize].len() == 0 {
             println!("{}: no data", name);
         } else {
-            println!("{}: Avg: {}, Min: {}, Max: {}", name, average(time_vec[solv.clone() as usize].as_slice()), 
-                time_vec[solv.clone() as usize].iter().min().unwrap(), time_vec[solv as usize].iter().max().unwrap());
+            // outliers (e.g. a run that met a cron job) are listed, not averaged in
+            let times = &time_vec[solv.clone() as usize];
+            let marked = stats::outliers(times);
+            let kept: Vec<u128> = times.iter().zip(marked.iter()).filter(|(_, o)| !**o).map(|(t, _)| *t).collect();
+            let dropped: Vec<u128> = times.iter().zip(marked.iter()).filter(|(_, o)| **o).map(|(t, _)| *t).collect();
+            println!("{}: Avg: {}, Median: {}, Min: {}, Max: {}, peak mem: {}{}{}", name, average(&kept), stats::median(times),
+                times.iter().min().unwrap(), times.iter().max().unwrap(),
+                megabytes(mem_vec[solv.clone() as usize].iter().copied().max().unwrap_or(0)),
+                gap(&cost_vec[solv.clone() as usize], &cost_vec[Solvers::RUST2 as usize]),
+                if dropped.is_empty() { String::new() } else { format!(", outliers: {:?}", dropped) });
+            println!("    {}", timing::summary(&timing_vec[solv as usize]));
         }
     }
-    Ok(())
+    console::chart(&Solvers::iter().filter(|s| !time_vec[s.clone() as usize].is_empty())
+        .map(|s| (s.clone().into(), stats::median(&time_vec[s.clone() as usize]), *time_vec[s as usize].iter().max().unwrap()))
+        .collect::<Vec<(&str, f64, u128)>>());
+    print_comparisons(time_vec, paired);
 }
+
+// Pairwise matrix of the solvers with enough runs: the effect size of the row against the column,
+// positive when the row is slower, starred when the difference is significant after Holm's adjustment
+fn print_comparisons(time_vec: &[Vec<u128>; SOLVERS], paired: bool) {
+    let solvers: Vec<Solvers> = Solvers::iter().filter(|s| time_vec[s.clone() as usize].len() >= MIN_COMPARED).collect();
+    if solvers.len() < 2 {
+        return;
+    }
+    let mut pairs: Vec<(usize, usize, stats::Comparison)> = vec![];
+    for i in 0..solvers.len() {
+        for j in i + 1..solvers.len() {
+            pairs.push((i, j, stats::compare(&time_vec[solvers[i].clone() as usize], &time_vec[solvers[j].clone() as usize], paired)));
+        }
+    }
+    let adjusted = stats::holm(&pairs.iter().map(|(_, _, c)| c.p).collect::<Vec<f64>>());
+    let mut cells: Vec<Vec<String>> = vec![vec!["-".to_string(); solvers.len()]; solvers.len()];
+    for ((i, j, c), p) in pairs.iter().zip(adjusted.iter()) {
+        let stars = if *p < 0.01 { "**" } else if *p < 0.05 { "*" } else { "" };
+        cells[*i][*j] = format!("{:+.2}{}", c.effect, stars);
+        cells[*j][*i] = format!("{:+.2}{}", 0.0 - c.effect, stars);
+    }
+    let paired = pairs.iter().all(|(_, _, c)| c.paired);
+    println!("Row against column, rank-biserial effect (+ row slower), * p < 0.05, ** p < 0.01 after Holm ({}):",
+        if paired { "Wilcoxon signed-rank, runs paired by instance and round" } else { "Mann-Whitney U" });
+    println!("{:>14}{}", "", (1..=solvers.len()).map(|j| format!("{:>8}", j)).collect::<String>());
+    for (i, row) in cells.iter().enumerate() {
+        let name: &'static str = solvers[i].clone().into();
+        println!("{:>3} {:<10}{}", i + 1, name, row.iter().map(|c| format!("{:>8}", c)).collect::<String>());
+    }
 }
 
-fn run_lcm(exp_val: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], cost_vec: &mut [Vec<u32>; 20], time_vec: &mut [Vec<u128>; 20]) {
+fn run_lcm(exp_val: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+           cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
     let min_size: usize = cmp::min(d_size, s_size);
     let start = Instant::now();
-    let (lcm_cost, ret) = lcm(&cost_arr, s_size, d_size);
+    let (lcm_cost, ret) = lcm(&cost_arr, forb, s_size, d_size);
     time_vec[Solvers::LCM as usize].push(start.elapsed().as_millis());
     if ret.len() != min_size || !no_duplicates(ret) {
-        println!("LCM: plan is invalid");
+        invalid("LCM: plan is invalid or infeasible".to_string());
     }
     // --------------- COMPARING RESULTS
     if exp_val != lcm_cost {
-        println!("LCM is worse rust: {} lcm: {}", exp_val, lcm_cost);
+        info(format!("LCM is worse rust: {} lcm: {}", exp_val, lcm_cost));
     }
     cost_vec[Solvers::LCM as usize].push(lcm_cost);
 }
 
-fn run_munkres(d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], cost_vec: &mut [Vec<u32>; 20], time_vec: &mut [Vec<u128>; 20]) -> u32 {
+fn run_lcm2(exp_val: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+            cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
     let min_size: usize = cmp::min(d_size, s_size);
     let start = Instant::now();
-    let munk = munkres(&cost_arr, s_size, d_size);
+    let (lcm_cost, ret) = lcm_sorted(cost_arr, forb, s_size, d_size);
+    phase("solve");
+    time_vec[Solvers::LCM2 as usize].push(start.elapsed().as_millis());
+    if ret.len() != min_size || !no_duplicates(ret) {
+        invalid("LCM2: plan is invalid or infeasible".to_string());
+    }
+    if exp_val != lcm_cost {
+        info(format!("LCM2 is worse rust: {} lcm: {}", exp_val, lcm_cost));
+    }
+    cost_vec[Solvers::LCM2 as usize].push(lcm_cost);
+    phase("validate");
+}
+
+// Greedy heuristics (see heuristics.rs), the plan is checked and compared with the exact cost
+fn run_heuristic(key: Solvers, solver: Heuristic, exp_cost: u32, inst: &Instance,
+                 cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let Instance { cost_arr, forb, s_size, d_size } = *inst;
+    let name: &'static str = key.clone().into();
+    let min_size: usize = cmp::min(d_size, s_size);
+    let start = Instant::now();
+    let (heur_cost, pairs) = solver(cost_arr, forb, s_size, d_size);
+    phase("solve");
+    time_vec[key.clone() as usize].push(start.elapsed().as_millis());
+    if !no_duplicates(pairs.iter().map(|(s, _)| s)) || !no_duplicates(pairs.iter().map(|(_, d)| d))
+        || pairs.iter().any(|(s, d)| forb.is_forbidden(*s, *d))
+        || pairs.iter().map(|(s, d)| cost_arr[*s][*d] as u32).sum::<u32>() != heur_cost {
+        invalid(format!("{}: plan is invalid", name));
+    } else if pairs.len() != min_size {
+        invalid(format!("{}: plan is infeasible, {} of {} pairs", name, pairs.len(), min_size));
+    }
+    if heur_cost != exp_cost {
+        info(format!("{} is worse, exact: {} {}: {} ({:.2}%)", name, exp_cost, name, heur_cost,
+            (heur_cost as f32 - exp_cost as f32) * 100.0 / exp_cost.max(1) as f32));
+    }
+    cost_vec[key as usize].push(heur_cost);
+    phase("validate");
+}
+
+fn run_munkres(d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+               cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) -> u32 {
+    let min_size: usize = cmp::min(d_size, s_size);
+    let start = Instant::now();
+    let munk = munkres(&cost_arr, forb, s_size, d_size);
     
     time_vec[Solvers::RUST as usize].push(start.elapsed().as_millis());
     let munk_cost: u32 = sum_up_cost(&munk, &cost_arr);
@@ -221,43 +676,229 @@ fn run_munkres(d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], c
             minus_count, demand_size, supply_size);
     }
     */
-    if values.len() != min_size || !no_duplicates(values) {
-        println!("Rust: plan is invalid");
+    if !no_duplicates(values.clone()) {
+        invalid("Rust: plan is invalid".to_string());
+    } else if values.len() != min_size {
+        invalid(format!("Rust: plan is infeasible, {} rows left unassigned", min_size - values.len()));
     }
     cost_vec[Solvers::RUST as usize].push(munk_cost);
     //println!("Munkres ({}): {:?}", munk_cost, munk);
     return munk_cost;
 }
 
-fn run_munkres2(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], 
-                cost_vec: &mut [Vec<u32>; 20], time_vec: &mut [Vec<u128>; 20]) -> u32 {
+// Munkres2 on the square matrix; the time of a run is its solve, building the weights is in the prepare phase
+// like building the graph of the solvers below
+fn run_munkres2(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+                cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) -> u32 {
     let max_size: usize = cmp::max(d_size, s_size);
+    let weights = munkres2_weights(cost_arr, forb, max_size, max_size);
+    phase("prepare");
     let start = Instant::now();
-    let (_, ret) = munkres2(&cost_arr, max_size, max_size);
+    let (_, ret) = kuhn_munkres_min(&weights);
+    phase("solve");
     time_vec[Solvers::RUST2 as usize].push(start.elapsed().as_millis());
     
-    let mut munk2_cost = 0;
-    for (s, d) in ret.iter().enumerate() {
-        if cost_arr[s][*d as usize] < BIG_VALUE {
-            munk2_cost += cost_arr[s][*d as usize] as u32;
-        }
-    }
+    let (munk2_cost, forbidden_count) = munkres2_cost(cost_arr, forb, &ret);
     cost_vec[Solvers::RUST2 as usize].push(munk2_cost);
+    if forbidden_count > 0 {
+        note(format!("Munkres2: plan is infeasible, {} rows can only get a forbidden pair", forbidden_count));
+    }
     if munk2_cost != exp_cost {
-        println!("Munkres2 cost is wrong, should be {}, is {}", munk2_cost, exp_cost);    
+        suboptimal(format!("Munkres2 cost is wrong, should be {}, is {}", exp_cost, munk2_cost));
     }
     
     //println!("Munkres2 ({}): {:?}", munk2_cost, ret);
+    phase("validate");
     return munk2_cost;
 }
 
-fn run_lapjv(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], 
-            cost_vec: &mut [Vec<u32>; 20], time_vec: &mut [Vec<u128>; 20]) {
+// The expected cost of an instance for the timed runs to be checked against: a Munkres2 solve, not timed
+fn reference_cost(size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) -> u32 {
+    let (_, ret) = munkres2(cost_arr, forb, size, size);
+    munkres2_cost(cost_arr, forb, &ret).0
+}
+
+// cost of a square Munkres2 plan without padding and forbidden pairs, and how many forbidden pairs it has
+fn munkres2_cost(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, ret: &[usize]) -> (u32, usize) {
+    let mut munk2_cost = 0;
+    let mut forbidden_count = 0;
+    for (s, d) in ret.iter().enumerate() {
+        if forb.is_forbidden(s, *d) {
+            forbidden_count += 1;
+        } else if cost_arr[s][*d] < BIG_VALUE {
+            munk2_cost += cost_arr[s][*d] as u32;
+        }
+    }
+    (munk2_cost, forbidden_count)
+}
+
+fn run_maxcard(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+               cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    phase("prepare");
+    let start = Instant::now();
+    let ret = min_cost_max_matching(&graph);
+    phase("solve");
+    time_vec[Solvers::MaxCard as usize].push(start.elapsed().as_millis());
+
+    let min_size: usize = cmp::min(d_size, s_size);
+    if ret.size() < min_size {
+        let rows = ret.unassigned_rows();
+        let cols = ret.unassigned_cols();
+        note(format!("MaxCard: infeasible, only {} of {} pairs possible", ret.size(), min_size));
+        info(format!("MaxCard: uncovered supply ({}): {:?}", rows.len(), &rows[..cmp::min(rows.len(), 20)]));
+        info(format!("MaxCard: uncovered demand ({}): {:?}", cols.len(), &cols[..cmp::min(cols.len(), 20)]));
+    }
+    cost_vec[Solvers::MaxCard as usize].push(ret.cost as u32);
+    if ret.cost as u32 != exp_cost {
+        suboptimal(format!("MaxCard cost is wrong, should be {}, is {}", exp_cost, ret.cost));
+    }
+    phase("validate");
+}
+
+fn run_auction(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+               cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    phase("prepare");
+    let start = Instant::now();
+    let ret = auction(&graph, threads);
+    phase("solve");
+    time_vec[Solvers::Auction as usize].push(start.elapsed().as_millis());
+
+    if !no_duplicates(ret.row_to_col.iter().flatten()) || ret.row_to_col.iter().enumerate().any(|(s, d)| d.is_some_and(|d| forb.is_forbidden(s, d))) {
+        invalid("Auction: plan is invalid".to_string());
+    } else if ret.size() < cmp::min(d_size, s_size) {
+        invalid(format!("Auction: plan is infeasible, {} of {} pairs", ret.size(), cmp::min(d_size, s_size)));
+    }
+    cost_vec[Solvers::Auction as usize].push(ret.cost as u32);
+    if ret.cost as u32 != exp_cost {
+        suboptimal(format!("Auction cost is wrong, should be {}, is {} ({} threads)", exp_cost, ret.cost, threads));
+    }
+    phase("validate");
+}
+
+// LP reference without GLPK (see network_simplex.rs)
+fn run_network_simplex(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+                       cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    phase("prepare");
+    let start = Instant::now();
+    let (ret, pivots) = ns_assignment(&graph);
+    phase("solve");
+    time_vec[Solvers::NetSimplex as usize].push(start.elapsed().as_millis());
+
+    if !no_duplicates(ret.row_to_col.iter().flatten()) || ret.row_to_col.iter().enumerate().any(|(s, d)| d.is_some_and(|d| forb.is_forbidden(s, d))) {
+        invalid("Network simplex: plan is invalid".to_string());
+    } else if ret.size() < cmp::min(d_size, s_size) {
+        invalid(format!("Network simplex: plan is infeasible, {} of {} pairs", ret.size(), cmp::min(d_size, s_size)));
+    }
+    cost_vec[Solvers::NetSimplex as usize].push(ret.cost as u32);
+    if ret.cost as u32 != exp_cost {
+        suboptimal(format!("Network simplex cost is wrong, should be {}, is {} ({} pivots)", exp_cost, ret.cost, pivots));
+    }
+    phase("validate");
+}
+
+static GLPK: OnceLock<bool> = OnceLock::new();
+
+// glpk.py needs cvxopt built with GLPK, checked once
+fn glpk_available() -> bool {
+    *GLPK.get_or_init(|| Command::new("sh").arg("-c").arg("python3 -c 'import cvxopt.glpk'").output().is_ok_and(|out| out.status.success()))
+}
+
+// Bottleneck plans (see bottleneck.rs), checked like Munkres2; the sum is compared with the exact one
+fn run_bottleneck(key: Solvers, solver: fn(&Graph) -> (Option<u32>, Assignment), exp_cost: u32, inst: &Instance,
+                  cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let Instance { cost_arr, forb, s_size, d_size } = *inst;
+    let name: &'static str = key.clone().into();
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    phase("prepare");
+    let start = Instant::now();
+    let (limit, ret) = solver(&graph);
+    phase("solve");
+    time_vec[key.clone() as usize].push(start.elapsed().as_millis());
+
+    let mut plan_cost: u32 = 0;
+    let mut forbidden_count = 0;
+    let mut longest: Option<u32> = None;
+    for (s, d) in ret.row_to_col.iter().enumerate() {
+        if let Some(d) = d {
+            if forb.is_forbidden(s, *d) {
+                forbidden_count += 1;
+            } else {
+                plan_cost += cost_arr[s][*d] as u32;
+                longest = longest.max(Some(cost_arr[s][*d] as u32));
+            }
+        }
+    }
+    if !no_duplicates(ret.row_to_col.iter().flatten()) || forbidden_count > 0 || longest != limit {
+        invalid(format!("{}: plan is invalid", name));
+    } else if ret.size() < cmp::min(d_size, s_size) {
+        invalid(format!("{}: plan is infeasible, {} of {} pairs", name, ret.size(), cmp::min(d_size, s_size)));
+    }
+    info(format!("{}: longest pair {:?}, cost {} exact {} (+{})", name, limit, plan_cost, exp_cost, plan_cost as i64 - exp_cost as i64));
+    cost_vec[key as usize].push(plan_cost);
+    phase("validate");
+}
+
+fn run_hybrid(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+              cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let start = Instant::now();
+    let limit = experiment::hybrid_limit();
+    let (ret, fixed) = hybrid(cost_arr, forb, s_size, d_size, limit, min_cost_max_matching);
+    phase("solve");
+    let elapsed = start.elapsed().as_millis();
+    time_vec[Solvers::Hybrid as usize].push(elapsed);
+    cost_vec[Solvers::Hybrid as usize].push(ret.cost as u32);
+
+    if !no_duplicates(ret.row_to_col.iter().flatten()) || ret.row_to_col.iter().enumerate().any(|(s, d)| d.is_some_and(|d| forb.is_forbidden(s, d))) {
+        invalid("Hybrid: plan is invalid".to_string());
+    } else if ret.size() < cmp::min(d_size, s_size) {
+        invalid(format!("Hybrid: plan is infeasible, {} of {} pairs", ret.size(), cmp::min(d_size, s_size)));
+    }
+    phase("validate");
+    // against the exact solver alone on the same instance, timed here after the run since the solver order is shuffled
+    if console::at(Verbosity::Verbose) {
+        let start = Instant::now();
+        min_cost_max_matching(&Graph::from_matrix(cost_arr, forb, s_size, d_size));
+        let exact_time = start.elapsed().as_millis();
+        info(format!("Hybrid ({:?}): {} pairs fixed by LCM, {} ms, exact {} ms, saved {} ms, cost {} exact {} ({} lost)", limit, fixed,
+            elapsed, exact_time, exact_time as i128 - elapsed as i128, ret.cost, exp_cost, ret.cost as i64 - exp_cost as i64));
+    }
+}
+
+fn run_murty(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+             cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let graph = Graph::from_matrix(cost_arr, forb, s_size, d_size);
+    phase("prepare");
+    let start = Instant::now();
+    let ret = k_best(&graph, experiment::k_best());
+    phase("solve");
+    time_vec[Solvers::Murty as usize].push(start.elapsed().as_millis());
+
+    let costs: Vec<u64> = ret.iter().map(|a| a.cost).collect();
+    info(format!("Murty: {} best costs: {:?}", ret.len(), costs));
+    if costs.windows(2).any(|w| w[0] > w[1]) || !no_duplicates(ret.iter().map(|a| a.row_to_col.clone())) {
+        note("Murty: plans are not distinct or not in order".to_string());
+    }
+    if !costs.is_empty() {
+        cost_vec[Solvers::Murty as usize].push(costs[0] as u32);
+        if costs[0] as u32 != exp_cost {
+            suboptimal(format!("Murty cost is wrong, should be {}, is {}", exp_cost, costs[0]));
+        }
+    }
+    phase("validate");
+}
+
+fn run_lapjv(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden,
+            cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
     let max_size: usize = cmp::max(d_size, s_size);
+    let big = big_m(cost_arr, forb, s_size, d_size);
     let mut vect: Vec<f32> = vec![];
     for s in 0..max_size {
         for d in 0..max_size {
-            vect.push(cost_arr[s][d] as f32);
+            vect.push(solver_cost(cost_arr, forb, big, s, d) as f32);
         }
     }
     let m = Array2::from_shape_vec((max_size, max_size), vect).unwrap();
@@ -268,51 +909,73 @@ fn run_lapjv(exp_cost: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZ
     time_vec[Solvers::RUST3 as usize].push(start.elapsed().as_millis());
     
     let mut munk3_cost = 0;
+    let mut forbidden_count = 0;
     for (s, d) in ret.0.iter().enumerate() {
-        if cost_arr[s][*d as usize] < BIG_VALUE {
-            munk3_cost += cost_arr[s][*d as usize] as u32;
+        if forb.is_forbidden(s, *d) {
+            forbidden_count += 1;
+        } else if cost_arr[s][*d] < BIG_VALUE {
+            munk3_cost += cost_arr[s][*d] as u32;
         }
     }
     cost_vec[Solvers::RUST3 as usize].push(munk3_cost);
+    if forbidden_count > 0 {
+        note(format!("Lapjv: plan is infeasible, {} rows can only get a forbidden pair", forbidden_count));
+    }
     if munk3_cost != exp_cost {
-        println!("Munkres2 cost is wrong, should be {}, is {}", munk3_cost, exp_cost);    
+        suboptimal(format!("Lapjv cost is wrong, should be {}, is {}", exp_cost, munk3_cost));
     }
     //assert_eq!(result.0, vec![2, 0, 1]);
     //assert_eq!(result.1, vec![1, 2, 0]);
 }
 
-fn run(cmd: &str, key: Solvers, exp_val: u32, d_size: usize, s_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE],
-       cost_vec: &mut [Vec<u32>; 20], time_vec: &mut [Vec<u128>; 20]) {
-    println!("{}...", cmd);
+fn run(cmd: &str, key: Solvers, exp_val: u32, inst: &Instance, cost_vec: &mut [Vec<u32>; SOLVERS], time_vec: &mut [Vec<u128>; SOLVERS]) {
+    let Instance { cost_arr, forb, s_size, d_size } = *inst;
+    info(format!("{}...", cmd));
     let max_size: usize = cmp::max(d_size, s_size);
     let min_size: usize = cmp::min(d_size, s_size);
     if key == Solvers::GLPK {
-        write_input_balanced("input.txt", max_size, &cost_arr);
+        write_input_balanced("input.txt", max_size, &cost_arr, forb);
     } else {
-        write_input("input.txt", s_size, d_size, &cost_arr);
+        write_input("input.txt", s_size, d_size, &cost_arr, forb);
+    }
+    // these scripts have the matrix in them, writing them is part of the prepare phase like input.txt
+    match key {
+        Solvers::PYTHON3 => generate_python3("munk3.py", s_size, d_size, cost_arr, forb),
+        Solvers::PYTHON5 => generate_python5("munk5.py", s_size, d_size, cost_arr, forb),
+        _ => {}
     }
     match remove_file("output.txt") { Ok(_) => {} Err(_) => {} };
+    phase("prepare");
     
-    Command::new("sh").arg("-c").arg(cmd).output().expect("failed to execute process");
+    if run_child(cmd).is_none() {
+        return; // killed at the timeout, output.txt is not there
+    }
+    let measured = phase("solve");
     
-    let (elapsed, sum, ret) =  match key {
-        Solvers::GLPK => read_results_binary("output.txt", max_size, &cost_arr),
-        Solvers::CPP2 => read_square_matrix("output.txt", &cost_arr),
-        Solvers::PYTHON => read_python_row_col("output.txt", &cost_arr),
-                        _  => read_results_index("output.txt", &cost_arr),
+    let (elapsed, sum, ret, forbidden_count) =  match key {
+        Solvers::GLPK => read_results_binary("output.txt", max_size, &cost_arr, forb),
+        Solvers::CPP2 => read_square_matrix("output.txt", &cost_arr, forb),
+        Solvers::PYTHON => read_python_row_col("output.txt", &cost_arr, forb),
+                        _  => read_results_index("output.txt", &cost_arr, forb),
                     };
+    phase("parse");
+    self_reported(cmd, elapsed, measured);
     time_vec[key.clone() as usize].push(elapsed);
     cost_vec[key as usize].push(sum);
     //println!("Returned ({}): {:?}", sum, ret);
     if ret.len() != min_size && ret.len() != max_size {
-        println!("Plan is invalid, expected size: {}, returned number of rows: {}", min_size, ret.len());
+        invalid(format!("Plan is invalid, expected size: {}, returned number of rows: {}", min_size, ret.len()));
     }
     if !no_duplicates(ret) {
-        println!("Plan is invalid, duplicated found");
+        invalid("Plan is invalid, duplicated found".to_string());
+    }
+    if forbidden_count > 0 {
+        invalid(format!("Plan is infeasible, {} forbidden pairs used", forbidden_count));
     }
     if exp_val != sum {
-        println!("{}: expected value {} != {}", cmd, exp_val, sum);
+        suboptimal(format!("{}: expected value {} != {}", cmd, exp_val, sum));
     }
+    phase("validate");
 }
 
 fn  rm_minusone(vec: &Vec<i16>) -> (i16, Vec<i16>) {
@@ -328,10 +991,10 @@ fn  rm_minusone(vec: &Vec<i16>) -> (i16, Vec<i16>) {
     return (counter, ret);
 }
 
-fn generate_python(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn generate_python(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "from munkres import Munkres\n").unwrap();
-    write_matrix(&mut writer, width, length, cost_arr);
+    write_matrix(&mut writer, width, length, cost_arr, forb);
     write!(&mut writer, "m = Munkres()\nindexes = m.compute(matrix)\n").unwrap();
     write!(&mut writer, "b = datetime.datetime.now()\nc = b - a\nmillis = int(c.total_seconds() * 1000)\n").unwrap();
     write!(&mut writer, "f = open(\"output.txt\", \"w\")\nf.write (\"%d\\n\" % (millis))\n").unwrap();
@@ -339,34 +1002,34 @@ fn generate_python(filename: &str, width: usize, length: usize, cost_arr: &[[u16
     write!(&mut writer, "f.close()\n").unwrap(); 
 }
 
-fn generate_python2(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn generate_python2(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "from lapjv import lapjv\n").unwrap();
-    write_matrix(&mut writer, width, length, cost_arr);
+    write_matrix(&mut writer, width, length, cost_arr, forb);
     write!(&mut writer, "row, col, _ = lapjv(matrix)\n").unwrap();
     write_output(&mut writer);
 }
 
-fn generate_python3(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn generate_python3(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "from scipy.optimize import linear_sum_assignment\n").unwrap();
-    write_matrix(&mut writer, width, length, cost_arr);
+    write_matrix(&mut writer, width, length, cost_arr, forb);
     write!(&mut writer, "_, row = linear_sum_assignment(matrix)\n").unwrap();
     write_output(&mut writer);
 }
 
-fn generate_python4(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn generate_python4(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "from lapsolver import solve_dense\n").unwrap();
-    write_matrix(&mut writer, width, length, cost_arr);
+    write_matrix(&mut writer, width, length, cost_arr, forb);
     write!(&mut writer, "row, _ = solve_dense(matrix)\n").unwrap();
     write_output(&mut writer);
 }
 
-fn generate_python5(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn generate_python5(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "import laptools\nfrom laptools import lap\n").unwrap();
-    write_matrix(&mut writer, width, length, cost_arr);
+    write_matrix(&mut writer, width, length, cost_arr, forb);
     write!(&mut writer, "_, row = lap.solve(matrix)\n").unwrap();
     write_output(&mut writer);
 }
@@ -379,12 +1042,13 @@ fn write_output(writer: &mut File) {
     write!(writer, "f.close()\n").unwrap();
 }
 
-fn write_matrix(writer: &mut File, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn write_matrix(writer: &mut File, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
+    let big = big_m(cost_arr, forb, width, length);
     write!(writer, "import datetime\nmatrix = [").unwrap();
     for s in 0 .. width {
         write!(writer, "[").unwrap();
         for d in 0 .. length {
-            write!(writer, "{}", cost_arr[s][d]).unwrap();
+            write!(writer, "{}", solver_cost(cost_arr, forb, big, s, d)).unwrap();
             if d < length -1 {
                 write!(writer, ",").unwrap();
             }
@@ -409,9 +1073,10 @@ fn sum_up_cost(vect: &Vec<i16>, cost_arr: &[[u16; DSIZE]; SSIZE]) -> u32 {
     return sum;
 }
 
-fn read_square_matrix(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u128, u32, Vec<i16>) {
+fn read_square_matrix(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) -> (u128, u32, Vec<i16>, usize) {
     let mut ret: Vec<i16> = vec![];
     let mut sum: u32 = 0;
+    let mut forbidden_count: usize = 0;
     let f = BufReader::new(File::open(filename).unwrap());
     let mut first_line = true; // elapsed time in the first line
     let mut elapsed: u128 = 0;
@@ -433,19 +1098,22 @@ fn read_square_matrix(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u128
             if flag == 1 {
                 let idx = j -2; // two whitespaces to be skipped
                 ret.push(j as i16);
-                if cost_arr[i-1][idx] < BIG_VALUE { // -1 as the first line contains elapsed time
+                if forb.is_forbidden(i-1, idx) { // -1 as the first line contains elapsed time
+                    forbidden_count += 1;
+                } else if cost_arr[i-1][idx] < BIG_VALUE {
                     sum += cost_arr[i-1][idx] as u32;
                 }
                 // we could break here from the inner loop
             }
         }
     }
-    return (elapsed, sum, ret);
+    return (elapsed, sum, ret, forbidden_count);
 }
 
-fn read_python_row_col(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u128, u32, Vec<i16>) {
+fn read_python_row_col(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) -> (u128, u32, Vec<i16>, usize) {
     let mut ret: Vec<i16> = vec![];
     let mut sum: u32 = 0;
+    let mut forbidden_count: usize = 0;
     let mut row: usize = 0;
     let mut col: usize;
     let mut first_line = true; // elapsed time in the first line
@@ -464,21 +1132,24 @@ fn read_python_row_col(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u12
             } else {
                 col = number.trim().parse::<usize>().unwrap();
                 ret.push(col as i16);
-                if cost_arr[row][col] < BIG_VALUE {
+                if forb.is_forbidden(row, col) {
+                    forbidden_count += 1;
+                } else if cost_arr[row][col] < BIG_VALUE {
                     sum += cost_arr[row][col] as u32;
                 }
             }
         }
     }
-    return (elapsed, sum, ret);
+    return (elapsed, sum, ret, forbidden_count);
 }
 
-fn read_results_binary(filename: &str, size: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u128, u32, Vec<i16>) {
+fn read_results_binary(filename: &str, size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) -> (u128, u32, Vec<i16>, usize) {
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
     let mut s: usize = 0;
     let mut d: usize = 0;
     let mut cost_sum: u32 = 0;
+    let mut forbidden_count: usize = 0;
     let mut ret: Vec<i16> = vec![];
     let mut first_line = true; // elapsed time in the first line
     let mut elapsed: u128 = 0;
@@ -491,7 +1162,9 @@ fn read_results_binary(filename: &str, size: usize, cost_arr: &[[u16; DSIZE]; SS
         let flag: usize = line.unwrap().parse().unwrap();
         if flag == 1 {
             ret.push(d as i16);
-            if cost_arr[s][d] < BIG_VALUE { // don't sup up fake assignments (non-balanced models)
+            if forb.is_forbidden(s, d) {
+                forbidden_count += 1;
+            } else if cost_arr[s][d] < BIG_VALUE { // don't sup up fake assignments (non-balanced models)
                 cost_sum += cost_arr[s][d] as u32;
             }
         }
@@ -500,7 +1173,7 @@ fn read_results_binary(filename: &str, size: usize, cost_arr: &[[u16; DSIZE]; SS
             s += 1;
         } else { d += 1; }
     }
-    return (elapsed, cost_sum, ret);
+    return (elapsed, cost_sum, ret, forbidden_count);
 }
 
 fn init_cost(cost_arr: &mut [[u16; DSIZE]; SSIZE], size: usize) {
@@ -511,25 +1184,34 @@ fn init_cost(cost_arr: &mut [[u16; DSIZE]; SSIZE], size: usize) {
     }
 }
 
-fn random_cost(cost_arr: &mut [[u16; DSIZE]; SSIZE], s_size: usize, d_size: usize) {
-    let mut rng = rand::thread_rng();
+fn random_cost(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &mut Forbidden, s_size: usize, d_size: usize) {
+    random_cost_in(cost_arr, forb, s_size, d_size, &COSTS, &mut rand::thread_rng());
+}
+
+fn random_cost_in(cost_arr: &mut [[u16; DSIZE]; SSIZE], forb: &mut Forbidden, s_size: usize, d_size: usize, costs: &CostRange,
+                  rng: &mut impl Rng) {
+    forb.clear();
     for s in 0 .. s_size { // supply
         for d in 0 .. d_size { // demand
-            cost_arr[s][d] = rng.gen_range(MIN_VALUE..MAX_VALUE); /*rng.gen_range(0..2); // sparsity 50%
-            if cost_arr[s][d] == 1 {
-                cost_arr[s][d] = rng.gen_range(1..MAX_VALUE);
+            if rng.gen_ratio(costs.forbidden_pct, 100) {
+                forb.forbid(s, d); // the cost below stays, it is just never used
             }
-            */
-            print!("{} ", cost_arr[s][d]);
+            cost_arr[s][d] = if rng.gen_ratio(costs.zero_pct, 100) { 0 } else { rng.gen_range(costs.min..costs.max) };
+        }
+    }
+    if console::at(Verbosity::Matrix) {
+        for row in cost_arr.iter().take(s_size) {
+            println!("{}", row[..d_size].iter().map(|c| c.to_string()).collect::<Vec<String>>().join(" "));
         }
     }
 }
 
-fn read_results_index(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u128, u32, Vec<i16>) {
+fn read_results_index(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) -> (u128, u32, Vec<i16>, usize) {
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
     let mut s: usize = 0;
     let mut cost_sum: u32 = 0;
+    let mut forbidden_count: usize = 0;
     let mut ret: Vec<i16> = vec![];
     let mut first_line = true; // elapsed time in the first line
     let mut elapsed: u128 = 0;
@@ -542,37 +1224,79 @@ fn read_results_index(filename: &str, cost_arr: &[[u16; DSIZE]; SSIZE]) -> (u128
         let index: i16 = line.unwrap().parse().unwrap();
         ret.push(index as i16);
         if index != -1 && index < DSIZE as i16 // such index means a fake customer in order to get the square matrix
+            && forb.is_forbidden(s, index as usize) {
+            forbidden_count += 1;
+        } else if index != -1 && index < DSIZE as i16
             && cost_arr[s][index as usize] < BIG_VALUE {
             cost_sum += cost_arr[s][index as usize] as u32;
         }
         s += 1;
     }
-    return (elapsed, cost_sum, ret);
+    return (elapsed, cost_sum, ret, forbidden_count);
 }
 
-fn write_input_balanced(filename: &str, size: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn write_input_balanced(filename: &str, size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
+    let big = big_m(cost_arr, forb, size, size);
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "{}\n", size).unwrap();
     for s in 0 .. size {
         for d in 0 .. size {
-            write!(&mut writer, "{} ", cost_arr[s][d]).unwrap();
+            write!(&mut writer, "{} ", solver_cost(cost_arr, forb, big, s, d)).unwrap();
         }
         write!(&mut writer, "\n").unwrap();
     }
 }
 
-fn write_input(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE]) {
+fn write_input(filename: &str, width: usize, length: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
+    let big = big_m(cost_arr, forb, width, length);
     let mut writer = File::create(filename).expect("creation failed");
     write!(&mut writer, "{} ", width).unwrap();
     write!(&mut writer, "{} ", length).unwrap();
     for s in 0 .. width {
         for d in 0 .. length {
-            write!(&mut writer, "{} ", cost_arr[s][d]).unwrap();
+            write!(&mut writer, "{} ", solver_cost(cost_arr, forb, big, s, d)).unwrap();
         }
     }
     writer.flush().unwrap();
 }
 
+// The instance with its forbidden pairs, unlike input.txt: sizes, then the costs row by row,
+// then the number of forbidden pairs and the pairs
+fn write_instance(filename: &str, s_size: usize, d_size: usize, cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden) {
+    let mut writer = std::io::BufWriter::new(File::create(filename).expect("creation failed"));
+    writeln!(&mut writer, "{} {}", s_size, d_size).unwrap();
+    for row in cost_arr.iter().take(s_size) {
+        writeln!(&mut writer, "{}", row[..d_size].iter().map(|c| c.to_string()).collect::<Vec<String>>().join(" ")).unwrap();
+    }
+    let pairs: Vec<(usize, usize)> = (0..s_size).flat_map(|s| (0..d_size).map(move |d| (s, d))).filter(|(s, d)| forb.is_forbidden(*s, *d)).collect();
+    writeln!(&mut writer, "{}", pairs.len()).unwrap();
+    for (s, d) in pairs {
+        writeln!(&mut writer, "{} {}", s, d).unwrap();
+    }
+    writer.flush().unwrap();
+}
+
+// Reads write_instance's file; the rest of the square of the larger size is padding
+fn read_instance(filename: &str, cost_arr: &mut [[u16; DSIZE]; SSIZE], s_size: usize, d_size: usize) -> Forbidden {
+    let max_size = cmp::max(s_size, d_size);
+    let text = std::fs::read_to_string(filename).expect("no instance file");
+    let mut numbers = text.split_whitespace().map(|n| n.parse::<usize>().expect("not a number"));
+    if numbers.next() != Some(s_size) || numbers.next() != Some(d_size) {
+        panic!("{} is not a {}x{} instance", filename, s_size, d_size);
+    }
+    for (s, row) in cost_arr.iter_mut().enumerate().take(max_size) {
+        for (d, cell) in row.iter_mut().enumerate().take(max_size) {
+            *cell = if s < s_size && d < d_size { numbers.next().expect("instance file too short") as u16 } else { BIG_VALUE };
+        }
+    }
+    let mut forb = Forbidden::new(max_size, max_size);
+    for _ in 0..numbers.next().unwrap_or(0) {
+        let (s, d) = (numbers.next().expect("instance file too short"), numbers.next().expect("instance file too short"));
+        forb.forbid(s, d);
+    }
+    forb
+}
+
 /*
 fn write_input_cpp(filename: &str, width: usize, length: usize, cost: &[[u16; DSIZE]; SSIZE]) {
     let mut writer = File::create(filename).expect("creation failed");
@@ -588,20 +1312,22 @@ fn write_input_cpp(filename: &str, width: usize, length: usize, cost: &[[u16; DS
 }
 */
 
-fn munkres(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize) -> Vec<i16> {
+// a row that could only get a forbidden pair is left unassigned (-1)
+fn munkres(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> Vec<i16> {
     let mut ret: Vec<i16> = vec![];
     let mut matrix: Vec<i32> = vec![];
+    let big = big_m(cost_arr, forb, cab_size, order_size);
     
     for s in 0 .. cab_size { // supply
         for d in 0 .. order_size { // demand
-            matrix.push(cost_arr[s][d] as i32);
+            matrix.push(solver_cost(cost_arr, forb, big, s, d) as i32);
         }
     }
     let assignment = minimize(&matrix, cab_size, order_size);
     
-    for s in assignment {
-        if s.is_some() {
-            ret.push(s.unwrap() as i16);
+    for (s, d) in assignment.iter().enumerate() {
+        if d.is_some() && !forb.is_forbidden(s, d.unwrap()) {
+            ret.push(d.unwrap() as i16);
         } else {
             ret.push(-1);
         }
@@ -609,18 +1335,23 @@ fn munkres(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize)
     return ret;
 }
 
-fn munkres2(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize) -> (i32, Vec<usize>) {
-    let mut matrix: Vec<Vec<i32>> = vec![];
+fn munkres2(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> (i64, Vec<usize>) {
+    kuhn_munkres_min(&munkres2_weights(cost_arr, forb, cab_size, order_size))
+}
+
+// the matrix pathfinding wants, forbidden pairs at big M; i64 as a plan of many of them overflows i32
+fn munkres2_weights(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> Matrix<i64> {
+    let mut matrix: Vec<Vec<i64>> = vec![];
+    let big = big_m(cost_arr, forb, cab_size, order_size);
     
     for s in 0 .. cab_size { // supply
-        let mut row: Vec<i32> = vec![];
+        let mut row: Vec<i64> = vec![];
         for d in 0 .. order_size { // demand
-            row.push(cost_arr[s][d] as i32);
+            row.push(solver_cost(cost_arr, forb, big, s, d) as i64);
         }
         matrix.push(row);
     }
-    let weights = Matrix::from_rows(matrix).unwrap();
-    return kuhn_munkres_min(&weights);
+    Matrix::from_rows(matrix).unwrap()
 }
 
 fn average(numbers: &[u128]) -> f32 {
@@ -631,6 +1362,15 @@ fn average(numbers: &[u128]) -> f32 {
     };
 }
 
+// Average optimality gap against the exact costs of the same iterations, empty when there is nothing to compare
+fn gap(costs: &[u32], exact: &[u32]) -> String {
+    if costs.is_empty() || costs.len() != exact.len() || costs == exact {
+        return String::new();
+    }
+    let gaps: Vec<f32> = costs.iter().zip(exact.iter()).map(|(c, e)| (*c as f32 - *e as f32) * 100.0 / (*e).max(1) as f32).collect();
+    format!(", gap: {:.2}%", gaps.iter().sum::<f32>() / gaps.len() as f32)
+}
+
 fn no_duplicates<T>(iter: T) -> bool
 where
     T: IntoIterator,
@@ -640,7 +1380,7 @@ where
     iter.into_iter().all(move |x| set.insert(x))
 }
 
-fn lcm(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize) -> (u32, Vec<usize>) {
+fn lcm(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, cab_size: usize, order_size: usize) -> (u32, Vec<usize>) {
     let mut cabs: [bool; SSIZE] = [false; SSIZE];
     let mut orders: [bool; DSIZE] = [false; DSIZE];
     let mut lcm_min_val;
@@ -657,7 +1397,7 @@ fn lcm(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize) ->
                 continue;
             }
             for order in 0..order_size {
-                if orders[order] == false && cost_arr[cab][order] < lcm_min_val {
+                if orders[order] == false && cost_arr[cab][order] < lcm_min_val && !forb.is_forbidden(cab, order) {
                     lcm_min_val = cost_arr[cab][order];
                     smin = cab;
                     dmin = order;
@@ -671,8 +1411,8 @@ fn lcm(cost_arr: &[[u16; DSIZE]; SSIZE], cab_size: usize, order_size: usize) ->
                 break; // yes, we could have loop labels and break two of them here, but this is for migration to C
             }
         }
-        if lcm_min_val == BIG_VALUE {
-            println!("LCM minimal cost is big_cost - no more interesting stuff here");
+        if smin == SSIZE {
+            info("LCM only forbidden pairs left - no more interesting stuff here".to_string());
             break;
         }
         // binding cab to the customer order
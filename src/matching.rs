@@ -0,0 +1,310 @@
+use std::collections::VecDeque;
+use crate::console::note;
+use crate::forbidden::Forbidden;
+use crate::sap::Sap;
+use crate::{BIG_VALUE, DSIZE, SSIZE};
+
+// Allowed (supply, demand) pairs with their costs, forbidden pairs are simply missing
+pub struct Graph {
+    pub rows: usize,
+    pub cols: usize,
+    pub adj: Vec<Vec<(usize, u32)>>,
+}
+
+impl Graph {
+    pub fn from_matrix(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, s_size: usize, d_size: usize) -> Graph {
+        let mut adj: Vec<Vec<(usize, u32)>> = Vec::with_capacity(s_size);
+        for (s, row) in cost_arr.iter().enumerate().take(s_size) {
+            let mut edges: Vec<(usize, u32)> = vec![];
+            for (d, val) in row.iter().enumerate().take(d_size) {
+                if !forb.is_forbidden(s, d) && *val < BIG_VALUE {
+                    edges.push((d, *val as u32));
+                }
+            }
+            adj.push(edges);
+        }
+        Graph { rows: s_size, cols: d_size, adj }
+    }
+
+    pub fn cost(&self, s: usize, d: usize) -> Option<u32> {
+        self.adj[s].iter().find(|(col, _)| *col == d).map(|(_, c)| *c)
+    }
+}
+
+// Result of the in-crate solvers; rows and columns left without a pair are not errors,
+// they are what a dispatcher has to handle (no cab for an order, no order for a cab)
+pub struct Assignment {
+    pub row_to_col: Vec<Option<usize>>,
+    pub col_to_row: Vec<Option<usize>>,
+    pub cost: u64,
+}
+
+impl Assignment {
+    pub fn new(row_to_col: Vec<Option<usize>>, cols: usize, graph: &Graph) -> Assignment {
+        let mut col_to_row: Vec<Option<usize>> = vec![None; cols];
+        let mut cost: u64 = 0;
+        for (s, d) in row_to_col.iter().enumerate() {
+            if let Some(d) = d {
+                col_to_row[*d] = Some(s);
+                cost += graph.cost(s, *d).expect("assignment uses a forbidden pair") as u64;
+            }
+        }
+        Assignment { row_to_col, col_to_row, cost }
+    }
+
+    pub fn size(&self) -> usize {
+        self.row_to_col.iter().filter(|d| d.is_some()).count()
+    }
+
+    pub fn unassigned_rows(&self) -> Vec<usize> {
+        self.row_to_col.iter().enumerate().filter(|(_, d)| d.is_none()).map(|(s, _)| s).collect()
+    }
+
+    pub fn unassigned_cols(&self) -> Vec<usize> {
+        self.col_to_row.iter().enumerate().filter(|(_, s)| s.is_none()).map(|(d, _)| d).collect()
+    }
+}
+
+// Square graph of size rows + cols with a perfect matching: columns 0..cols are the real ones,
+// cols + s is "supply s unassigned"; rows 0..rows are the real ones, rows + d is "demand d unassigned"
+// "Unassigned" costs 'big', which must be more than any plan so that plans stay maximal.
+pub fn extend(graph: &Graph, big: u32) -> Graph {
+    let mut adj: Vec<Vec<(usize, u32)>> = Vec::with_capacity(graph.rows + graph.cols);
+    for (s, edges) in graph.adj.iter().enumerate() {
+        let mut row = edges.clone();
+        row.push((graph.cols + s, big));
+        adj.push(row);
+    }
+    let mut dummies: Vec<Vec<(usize, u32)>> = (0..graph.cols).map(|d| vec![(d, 0)]).collect();
+    for (s, edges) in graph.adj.iter().enumerate() {
+        for (d, _) in edges {
+            dummies[*d].push((graph.cols + s, 0));
+        }
+    }
+    adj.extend(dummies);
+    let size = graph.rows + graph.cols;
+    Graph { rows: size, cols: size, adj }
+}
+
+// Maximum number of pairs that can be made of allowed cells, costs are ignored
+pub fn hopcroft_karp(graph: &Graph) -> (usize, Vec<Option<usize>>) {
+    let mut row_match: Vec<Option<usize>> = vec![None; graph.rows];
+    let mut col_match: Vec<Option<usize>> = vec![None; graph.cols];
+    let mut dist: Vec<usize> = vec![usize::MAX; graph.rows];
+    let mut size: usize = 0;
+    // greedy start, on dense instances it leaves only a few rows for the phases
+    for (s, edges) in graph.adj.iter().enumerate() {
+        if let Some((d, _)) = edges.iter().find(|(d, _)| col_match[*d].is_none()) {
+            row_match[s] = Some(*d);
+            col_match[*d] = Some(s);
+            size += 1;
+        }
+    }
+    loop {
+        // BFS from all free rows builds the layers of shortest augmenting paths
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for s in 0..graph.rows {
+            if row_match[s].is_none() {
+                dist[s] = 0;
+                queue.push_back(s);
+            } else {
+                dist[s] = usize::MAX;
+            }
+        }
+        let mut found = false;
+        while let Some(s) = queue.pop_front() {
+            for (d, _) in &graph.adj[s] {
+                match col_match[*d] {
+                    None => found = true,
+                    Some(s2) => {
+                        if dist[s2] == usize::MAX {
+                            dist[s2] = dist[s] + 1;
+                            queue.push_back(s2);
+                        }
+                    }
+                }
+            }
+        }
+        if !found {
+            break;
+        }
+        // DFS along the layers, vertex-disjoint paths only
+        let mut next_edge: Vec<usize> = vec![0; graph.rows];
+        for s in 0..graph.rows {
+            if row_match[s].is_none() && augment_layered(graph, s, &mut dist, &mut next_edge, &mut row_match, &mut col_match) {
+                size += 1;
+            }
+        }
+    }
+    (size, row_match)
+}
+
+// The cheapest of the largest plans: Hopcroft-Karp tells how many pairs can be made at all,
+// shortest augmenting paths then find the cheapest plan of exactly that size
+pub fn min_cost_max_matching(graph: &Graph) -> Assignment {
+    let (max_size, _) = hopcroft_karp(graph);
+    let mut sap = Sap::new(graph.rows, graph.cols);
+    sap.reduce_and_match(graph, max_size == graph.rows, max_size == graph.cols);
+    sap.solve(graph, max_size);
+    if sap.size() != max_size {
+        note(format!("Shortest paths found {} pairs, Hopcroft-Karp {}", sap.size(), max_size));
+    }
+    Assignment::new(sap.row_match, graph.cols, graph)
+}
+
+// What consecutive, similar batches can pass on: potentials and the plan of the last solve.
+// Any part may be missing or come from an instance of another size, it is repaired, not trusted.
+#[derive(Clone, Default)]
+pub struct WarmStart {
+    pub row_pot: Option<Vec<i64>>, // only used when column potentials are missing
+    pub col_pot: Option<Vec<i64>>,
+    pub row_to_col: Option<Vec<Option<usize>>>,
+}
+
+// Same plan as 'min_cost_max_matching', but starting from a hint; returns the hint for the next batch
+pub fn min_cost_max_matching_warm(graph: &Graph, hint: &WarmStart) -> (Assignment, WarmStart) {
+    let (max_size, _) = hopcroft_karp(graph);
+    // columns as given, or derived from row potentials as the highest ones keeping edges non-negative
+    let col_pot: Vec<i64> = match (&hint.col_pot, &hint.row_pot) {
+        (Some(pot), _) => (0..graph.cols).map(|d| pot.get(d).copied().unwrap_or(0)).collect(),
+        (None, Some(pot)) => {
+            let mut col_pot: Vec<i64> = vec![i64::MAX; graph.cols];
+            for (s, edges) in graph.adj.iter().enumerate() {
+                for (d, c) in edges {
+                    col_pot[*d] = col_pot[*d].min(*c as i64 + pot.get(s).copied().unwrap_or(0));
+                }
+            }
+            col_pot.iter().map(|p| if *p == i64::MAX { 0 } else { *p }).collect()
+        }
+        (None, None) => vec![0; graph.cols],
+    };
+    let mut sap = Sap::new(graph.rows, graph.cols);
+    sap.warm_start(graph, col_pot, hint.row_to_col.as_deref(), max_size == graph.rows, max_size == graph.cols);
+    sap.solve(graph, max_size);
+    if sap.size() != max_size {
+        note(format!("Shortest paths found {} pairs, Hopcroft-Karp {}", sap.size(), max_size));
+    }
+    let next = WarmStart {
+        row_pot: Some(sap.row_pot.clone()),
+        col_pot: Some(sap.col_pot.clone()),
+        row_to_col: Some(sap.row_match.clone()),
+    };
+    (Assignment::new(sap.row_match, graph.cols, graph), next)
+}
+
+// iterative, recursion would blow the stack on long paths in large instances
+fn augment_layered(graph: &Graph, root: usize, dist: &mut [usize], next_edge: &mut [usize],
+                   row_match: &mut [Option<usize>], col_match: &mut [Option<usize>]) -> bool {
+    let mut stack: Vec<usize> = vec![]; // rows on the path before 's'
+    let mut s = root;
+    loop {
+        let mut advanced = false;
+        while next_edge[s] < graph.adj[s].len() {
+            let d = graph.adj[s][next_edge[s]].0;
+            next_edge[s] += 1;
+            match col_match[d] {
+                None => {
+                    // free column reached, flip the path
+                    let mut d = d;
+                    let mut s = s;
+                    loop {
+                        let prev = row_match[s];
+                        row_match[s] = Some(d);
+                        col_match[d] = Some(s);
+                        match stack.pop() {
+                            Some(s_prev) => {
+                                d = prev.unwrap(); // the column 's_prev' used to get to 's'
+                                s = s_prev;
+                            }
+                            None => return true,
+                        }
+                    }
+                }
+                Some(s2) => {
+                    if dist[s2] == dist[s] + 1 {
+                        stack.push(s);
+                        s = s2;
+                        advanced = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if !advanced {
+            dist[s] = usize::MAX; // dead end, don't visit again in this phase
+            match stack.pop() {
+                Some(s_prev) => s = s_prev,
+                None => return false,
+            }
+        }
+    }
+}
+
+// Brute force for the tests of the solvers: the costs of every plan of the maximum size, cheapest first
+#[cfg(test)]
+pub fn all_plan_costs(graph: &Graph) -> Vec<u64> {
+    // (size, cost) of every plan, each row given a free allowed column or none
+    fn plans(graph: &Graph, s: usize, used: &mut Vec<bool>, size: usize, cost: u64, ret: &mut Vec<(usize, u64)>) {
+        if s == graph.rows {
+            ret.push((size, cost));
+            return;
+        }
+        plans(graph, s + 1, used, size, cost, ret);
+        for (d, c) in &graph.adj[s] {
+            if !used[*d] {
+                used[*d] = true;
+                plans(graph, s + 1, used, size + 1, cost + *c as u64, ret);
+                used[*d] = false;
+            }
+        }
+    }
+    let mut all = vec![];
+    plans(graph, 0, &mut vec![false; graph.cols], 0, 0, &mut all);
+    let max_size = all.iter().map(|(size, _)| *size).max().unwrap_or(0);
+    let mut ret: Vec<u64> = all.into_iter().filter(|(size, _)| *size == max_size).map(|(_, cost)| cost).collect();
+    ret.sort_unstable();
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::{test_cost_arr, test_instance};
+    use super::{min_cost_max_matching, min_cost_max_matching_warm, Graph, WarmStart};
+
+    // the next batch: a third of the costs changed and a few more pairs forbidden, some of them in the old plan
+    #[test]
+    fn warm_start_matches_cold_solve() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(10);
+        for (s_size, d_size, forbidden_pct) in [(6, 6, 0), (10, 10, 20), (5, 9, 20), (9, 5, 30)] {
+            for _ in 0..10 {
+                let mut forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 50, forbidden_pct);
+                let (_, hint) = min_cost_max_matching_warm(&Graph::from_matrix(&cost_arr, &forb, s_size, d_size), &WarmStart::default());
+                for (s, row) in cost_arr.iter_mut().enumerate().take(s_size) {
+                    for (d, val) in row.iter_mut().enumerate().take(d_size) {
+                        if rng.gen_ratio(1, 3) {
+                            *val = rng.gen_range(0..=50);
+                        }
+                        if rng.gen_ratio(1, 10) {
+                            forb.forbid(s, d);
+                        }
+                    }
+                }
+                let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                let cold = min_cost_max_matching(&graph);
+                let hints = [
+                    ("plan", hint.clone()),
+                    ("potentials", WarmStart { row_to_col: None, ..hint.clone() }),
+                    ("row potentials", WarmStart { col_pot: None, row_to_col: None, ..hint.clone() }),
+                ];
+                for (what, hint) in hints {
+                    let (warm, _) = min_cost_max_matching_warm(&graph, &hint);
+                    assert_eq!(warm.size(), cold.size(), "{}x{} from {}", s_size, d_size, what);
+                    assert_eq!(warm.cost, cold.cost, "{}x{} from {}", s_size, d_size, what);
+                }
+            }
+        }
+    }
+}
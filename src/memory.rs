@@ -0,0 +1,144 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use crate::console::note;
+use crate::isolation::pin_child;
+use crate::timing::{self, duration, Timing};
+use crate::{Solvers, SOLVERS};
+
+// Peak memory of a solver run. In-process solvers: the highest heap use above what was live when
+// the run started, seen by this allocator. External ones: the peak resident set of the child
+// process, from wait4 (the static cost matrix is in neither).
+pub struct Tracking;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static CHILD_PEAK: AtomicU64 = AtomicU64::new(0);
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(0); // of the next child processes, 0: none
+static TIMED_OUT: AtomicBool = AtomicBool::new(false);
+
+#[global_allocator]
+static ALLOC: Tracking = Tracking;
+
+fn grow(size: usize) {
+    let now = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for Tracking {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                grow(new_size - layout.size());
+            } else {
+                CURRENT.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+// Runs one solver and keeps its peak memory in bytes: the child's if it started one, else the heap's;
+// and its CPU time and phases (see timing.rs)
+pub fn measured<T>(key: Solvers, mem_vec: &mut [Vec<u64>; SOLVERS], timing_vec: &mut [Vec<Timing>; SOLVERS],
+                   run: impl FnOnce() -> T) -> T {
+    let base = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(base, Ordering::Relaxed);
+    CHILD_PEAK.store(0, Ordering::Relaxed);
+    timing::begin();
+    let ret = run();
+    timing_vec[key.clone() as usize].push(timing::end());
+    let child = CHILD_PEAK.load(Ordering::Relaxed);
+    let heap = (PEAK.load(Ordering::Relaxed) - base) as u64;
+    mem_vec[key as usize].push(if child > 0 { child } else { heap });
+    ret
+}
+
+// Solver processes running longer are killed, see run_child
+pub fn set_timeout(timeout: Option<Duration>) {
+    TIMEOUT_MS.store(timeout.map_or(0, |t| t.as_millis() as u64), Ordering::Relaxed);
+}
+
+// whether a child process was killed at the timeout since the last call
+pub fn timed_out() -> bool {
+    TIMED_OUT.swap(false, Ordering::Relaxed)
+}
+
+// 'sh -c cmd' like Command::output, but waited for with wait4 to get the peak resident set; returns stdout,
+// None if it was killed at the timeout. With a timeout it gets a process group, which is killed as a whole.
+#[allow(clippy::zombie_processes)] // reaped by wait4, not by Child::wait
+pub fn run_child(cmd: &str) -> Option<String> {
+    let timeout = TIMEOUT_MS.load(Ordering::Relaxed);
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd).stdout(Stdio::piped()).stderr(Stdio::null());
+    if timeout > 0 {
+        command.process_group(0);
+    }
+    pin_child(&mut command);
+    let mut child = command.spawn().expect("failed to execute process");
+    let pid = child.id() as libc::pid_t;
+    let (finished, watch) = mpsc::channel::<()>();
+    let watchdog = (timeout > 0).then(|| thread::spawn(move || {
+        let expired = matches!(watch.recv_timeout(Duration::from_millis(timeout)), Err(RecvTimeoutError::Timeout));
+        if expired {
+            unsafe { libc::kill(-pid, libc::SIGKILL) };
+        }
+        expired
+    }));
+    let mut out = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = stdout.read_to_string(&mut out);
+    }
+    let mut status: libc::c_int = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let waited = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+    drop(finished);
+    if watchdog.is_some_and(|w| w.join().unwrap_or(false)) {
+        note(format!("{}: killed after the timeout of {} s", cmd, timeout as f64 / 1000.0));
+        TIMED_OUT.store(true, Ordering::Relaxed);
+        return None;
+    }
+    if waited < 0 {
+        note(format!("{}: wait4 failed", cmd));
+        return Some(out);
+    }
+    if libc::WIFSIGNALED(status) {
+        note(format!("{}: killed by signal {}", cmd, libc::WTERMSIG(status)));
+    }
+    // kilobytes on Linux, bytes on macOS
+    let rss = if cfg!(target_os = "macos") { usage.ru_maxrss as u64 } else { usage.ru_maxrss as u64 * 1024 };
+    CHILD_PEAK.fetch_max(rss, Ordering::Relaxed);
+    timing::child_cpu(duration(usage.ru_utime), duration(usage.ru_stime));
+    Some(out)
+}
+
+pub fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
@@ -0,0 +1,125 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::console::note;
+use crate::matching::{extend, Assignment, Graph};
+use crate::sap::{Restrictions, Sap};
+
+// Murty's k-best assignments: the space of plans is split by forcing the first pairs of the best
+// plan and banning the next one; the best plan of each part is the candidate for the next place.
+// Only plans of the maximum size are enumerated (see 'min_cost_max_matching').
+//
+// Rectangular and sparse instances are made square and always perfectly matchable first:
+// every supply row gets its own "unassigned" column for a big cost, every demand column its own
+// "unassigned" row for free, and those dummies pair up along the transposed allowed pairs.
+// A child of a solved part then needs a single augmentation from the parent's matching (Miller, Stone, Cox).
+// Children wait in the queue with the parent's cost as a lower bound until they get to the top.
+pub fn k_best(graph: &Graph, k: usize) -> Vec<Assignment> {
+    let max_cost = graph.adj.iter().flat_map(|edges| edges.iter().map(|(_, c)| *c)).max().unwrap_or(0);
+    let ext = extend(graph, max_cost * graph.rows.min(graph.cols) as u32 + 1); // more than any plan
+    let size = ext.rows;
+    let mut ret: Vec<Assignment> = vec![];
+    let mut parts: Vec<Part> = vec![];
+    // cost, 1 when the cost is only the parent's one (a lower bound), parent part, split
+    let mut heap: BinaryHeap<Reverse<(i64, u8, usize, usize)>> = BinaryHeap::new();
+
+    let mut sap = Sap::new(size, size);
+    sap.reduce_and_match(&ext, true, false);
+    sap.solve(&ext, size);
+    let mut next = Some((Restrictions::new(size), sap));
+    let pairs_max = real_pairs(next.as_ref().unwrap().1.row_match.as_slice(), graph).len();
+
+    while ret.len() < k {
+        if let Some((restr, sap)) = next.take() {
+            let pairs = real_pairs(&sap.row_match, graph);
+            let mut row_to_col: Vec<Option<usize>> = vec![None; graph.rows];
+            for (s, d) in &pairs {
+                row_to_col[*s] = Some(*d);
+            }
+            ret.push(Assignment::new(row_to_col, graph.cols, graph));
+            // children are costed lazily, most of them never get to the top
+            for (j, (s, _)) in pairs.iter().enumerate() {
+                if restr.forced[*s].is_none() { // pairs forced by an ancestor are not split on
+                    heap.push(Reverse((sap.cost(), 1, parts.len(), j)));
+                }
+            }
+            parts.push(Part { restr, sap, pairs });
+        }
+        let Some(Reverse((cost, bound, parent, split))) = heap.pop() else {
+            break; // all plans of the maximum size enumerated
+        };
+        let child = parts[parent].child(split, &ext, graph, pairs_max);
+        if bound == 1 {
+            if let Some((_, sap)) = child {
+                heap.push(Reverse((sap.cost(), 0, parent, split)));
+            }
+        } else {
+            next = child; // same augmentation as when it was costed
+            if next.as_ref().map(|(_, sap)| sap.cost()) != Some(cost) {
+                note(format!("Murty: part re-solved for a different cost, expected {}", cost));
+            }
+        }
+    }
+    ret
+}
+
+// A part of the plan space that has been taken, with the solver state of its best plan
+struct Part {
+    restr: Restrictions,
+    sap: Sap,
+    pairs: Vec<(usize, usize)>,
+}
+
+impl Part {
+    // Best plan with the pairs before 'split' forced and the one at 'split' banned,
+    // one augmentation from this part's plan. None if no plan of the maximum size is left.
+    fn child(&self, split: usize, ext: &Graph, graph: &Graph, pairs_max: usize) -> Option<(Restrictions, Sap)> {
+        let mut restr = self.restr.clone();
+        for (s, d) in &self.pairs[..split] {
+            restr.forced[*s] = Some(*d);
+        }
+        let (s, d) = self.pairs[split];
+        restr.banned[s].push(d);
+        let mut sap = self.sap.clone();
+        sap.restr = Some(restr);
+        sap.unpair(s);
+        if !sap.augment(ext) || real_pairs(&sap.row_match, graph).len() != pairs_max {
+            return None;
+        }
+        let restr = sap.restr.take().unwrap();
+        Some((restr, sap))
+    }
+}
+
+fn real_pairs(row_match: &[Option<usize>], graph: &Graph) -> Vec<(usize, usize)> {
+    row_match[..graph.rows].iter().enumerate()
+        .filter_map(|(s, d)| d.filter(|d| *d < graph.cols).map(|d| (s, d))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{all_plan_costs, Graph};
+    use crate::{test_cost_arr, test_instance};
+    use super::k_best;
+
+    // every plan of the maximum size in order, square and rectangular, with and without forbidden pairs
+    #[test]
+    fn k_best_enumerates_all_plans() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(1);
+        for (s_size, d_size, forbidden_pct) in [(3, 3, 0), (4, 4, 20), (5, 5, 0), (6, 6, 30), (3, 5, 0), (5, 3, 20), (4, 6, 40)] {
+            for _ in 0..3 {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 20, forbidden_pct);
+                let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                let expected = all_plan_costs(&graph);
+                let plans = k_best(&graph, expected.len() + 1);
+                assert_eq!(plans.iter().map(|p| p.cost).collect::<Vec<u64>>(), expected, "{}x{}", s_size, d_size);
+                let mut distinct: Vec<&Vec<Option<usize>>> = plans.iter().map(|p| &p.row_to_col).collect();
+                distinct.sort();
+                distinct.dedup();
+                assert_eq!(distinct.len(), plans.len(), "{}x{}: a plan came twice", s_size, d_size);
+            }
+        }
+    }
+}
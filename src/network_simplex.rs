@@ -0,0 +1,279 @@
+use std::cmp;
+use crate::forbidden::Forbidden;
+use crate::matching::{Assignment, Graph};
+use crate::{DSIZE, SSIZE};
+
+// Primal network simplex on the bipartite graph, the LP reference that needs no GLPK.
+// Rows send their supply, columns take their demand; one extra node (the root of the spanning
+// tree) takes what is left over: row -> root is "supply unused", root -> column "demand unmet",
+// both at 'big', more than any plan, so the largest plan is the cheapest one.
+// Arcs are uncapacitated, the quantities bound the flow. Trees are kept strongly feasible
+// (Cunningham's rule), which avoids cycling on the very degenerate assignment problem.
+pub struct NetworkSimplex {
+    rows: usize,
+    cols: usize,
+    from: Vec<usize>,
+    to: Vec<usize>,
+    cost: Vec<i64>,
+    flow: Vec<u64>,
+    in_tree: Vec<bool>,
+    parent: Vec<usize>, // usize::MAX for the root
+    parent_arc: Vec<usize>,
+    depth: Vec<usize>,
+    pot: Vec<i64>, // cost + pot[from] - pot[to] is 0 on tree arcs
+    tree: Vec<Vec<usize>>, // tree arcs at a node
+    pub pivots: usize,
+}
+
+impl NetworkSimplex {
+    pub fn new(graph: &Graph, supply: &[u32], demand: &[u32]) -> NetworkSimplex {
+        let (rows, cols) = (graph.rows, graph.cols);
+        let root = rows + cols;
+        let max_cost = graph.adj.iter().flatten().map(|(_, c)| *c as i64).max().unwrap_or(0);
+        let units = cmp::min(supply.iter().map(|q| *q as i64).sum::<i64>(), demand.iter().map(|q| *q as i64).sum::<i64>());
+        let big = max_cost * units + 1;
+        let mut ns = NetworkSimplex {
+            rows,
+            cols,
+            from: vec![],
+            to: vec![],
+            cost: vec![],
+            flow: vec![],
+            in_tree: vec![],
+            parent: vec![root; root + 1],
+            parent_arc: vec![0; root + 1],
+            depth: vec![1; root + 1],
+            pot: vec![0; root + 1],
+            tree: vec![vec![]; root + 1],
+            pivots: 0,
+        };
+        ns.parent[root] = usize::MAX;
+        ns.depth[root] = 0;
+        // the first tree: every row and column hangs on the root with all its quantity;
+        // a row without supply hangs on an arc from the root, so that no arc with nothing on it points to the root
+        for (s, q) in supply.iter().enumerate() {
+            let arc = if *q > 0 { ns.add_arc(s, root, big, *q as u64) } else { ns.add_arc(root, s, 0, 0) };
+            ns.hang(s, arc);
+        }
+        for (d, q) in demand.iter().enumerate() {
+            let arc = ns.add_arc(root, rows + d, big, *q as u64);
+            ns.hang(rows + d, arc);
+        }
+        for (s, edges) in graph.adj.iter().enumerate() {
+            if supply[s] > 0 {
+                for (d, c) in edges {
+                    ns.add_arc(s, rows + d, *c as i64, 0);
+                }
+            }
+        }
+        ns
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, cost: i64, flow: u64) -> usize {
+        self.from.push(from);
+        self.to.push(to);
+        self.cost.push(cost);
+        self.flow.push(flow);
+        self.in_tree.push(false);
+        self.from.len() - 1
+    }
+
+    fn hang(&mut self, node: usize, arc: usize) {
+        let root = self.rows + self.cols;
+        self.in_tree[arc] = true;
+        self.parent_arc[node] = arc;
+        self.pot[node] = if self.from[arc] == root { self.cost[arc] } else { -self.cost[arc] };
+        self.tree[node].push(arc);
+        self.tree[root].push(arc);
+    }
+
+    fn reduced(&self, arc: usize) -> i64 {
+        self.cost[arc] + self.pot[self.from[arc]] - self.pot[self.to[arc]]
+    }
+
+    // Pivots until no arc has a negative reduced cost. Block pricing: the most negative arc
+    // of a block of about sqrt(arcs), blocks taken round robin.
+    pub fn solve(&mut self) {
+        let arcs = self.from.len();
+        let block = cmp::max((arcs as f64).sqrt() as usize, 10);
+        let mut next: usize = 0;
+        loop {
+            let mut entering: Option<usize> = None;
+            let mut best: i64 = 0;
+            let mut scanned: usize = 0;
+            while scanned < arcs {
+                let end = cmp::min(next + block, arcs);
+                for arc in next..end {
+                    if !self.in_tree[arc] {
+                        let rc = self.reduced(arc);
+                        if rc < best {
+                            best = rc;
+                            entering = Some(arc);
+                        }
+                    }
+                }
+                scanned += end - next;
+                next = if end == arcs { 0 } else { end };
+                if entering.is_some() {
+                    break;
+                }
+            }
+            match entering {
+                Some(arc) => self.pivot(arc),
+                None => return,
+            }
+        }
+    }
+
+    fn pivot(&mut self, entering: usize) {
+        self.pivots += 1;
+        let (i, j) = (self.from[entering], self.to[entering]);
+        // the cycle goes i -> j along the entering arc, then up from j to the apex and down to i.
+        // Blocking arcs are those against that direction; the last one met from the apex leaves:
+        // nearest to i on i's side, nearest to the apex on j's side, j's side on ties
+        let mut theta: u64 = u64::MAX;
+        let mut leaving: Option<(usize, bool)> = None; // child end of the arc, on i's side
+        let (mut a, mut b) = (i, j);
+        let mut a_path: Vec<usize> = vec![];
+        let mut b_path: Vec<usize> = vec![];
+        while a != b {
+            if self.depth[a] >= self.depth[b] {
+                a_path.push(a);
+                a = self.parent[a];
+            } else {
+                b_path.push(b);
+                b = self.parent[b];
+            }
+        }
+        for x in &a_path {
+            // going down to i: blocking when the arc points up
+            let arc = self.parent_arc[*x];
+            if self.from[arc] == *x && self.flow[arc] < theta {
+                theta = self.flow[arc];
+                leaving = Some((*x, true));
+            }
+        }
+        for x in &b_path {
+            // going up from j: blocking when the arc points down
+            let arc = self.parent_arc[*x];
+            if self.to[arc] == *x && self.flow[arc] <= theta {
+                theta = self.flow[arc];
+                leaving = Some((*x, false));
+            }
+        }
+        let (child, on_i_side) = leaving.expect("network simplex: unbounded cycle");
+        if theta > 0 {
+            self.flow[entering] += theta;
+            for x in &a_path {
+                let arc = self.parent_arc[*x];
+                if self.from[arc] == *x { self.flow[arc] -= theta } else { self.flow[arc] += theta }
+            }
+            for x in &b_path {
+                let arc = self.parent_arc[*x];
+                if self.to[arc] == *x { self.flow[arc] -= theta } else { self.flow[arc] += theta }
+            }
+        }
+
+        // the subtree below the leaving arc is hung on the entering arc instead
+        let leaving_arc = self.parent_arc[child];
+        self.in_tree[leaving_arc] = false;
+        let parent = self.parent[child];
+        self.tree[child].retain(|arc| *arc != leaving_arc);
+        self.tree[parent].retain(|arc| *arc != leaving_arc);
+        self.in_tree[entering] = true;
+        self.tree[i].push(entering);
+        self.tree[j].push(entering);
+        let (top, outside) = if on_i_side { (i, j) } else { (j, i) };
+        let delta = if top == j {
+            self.pot[i] + self.cost[entering] - self.pot[j]
+        } else {
+            self.pot[j] - self.cost[entering] - self.pot[i]
+        };
+        self.parent[top] = outside;
+        self.parent_arc[top] = entering;
+        let mut stack: Vec<usize> = vec![top];
+        while let Some(x) = stack.pop() {
+            self.depth[x] = self.depth[self.parent[x]] + 1;
+            self.pot[x] += delta;
+            for k in 0..self.tree[x].len() {
+                let arc = self.tree[x][k];
+                let y = if self.from[arc] == x { self.to[arc] } else { self.from[arc] };
+                if y != self.parent[x] {
+                    self.parent[y] = x;
+                    self.parent_arc[y] = arc;
+                    stack.push(y);
+                }
+            }
+        }
+    }
+
+    // (row, column, units) of every real arc with flow on it
+    pub fn flows(&self) -> Vec<(usize, usize, u32)> {
+        let root = self.rows + self.cols;
+        (0..self.from.len()).filter(|arc| self.flow[*arc] > 0 && self.from[*arc] != root && self.to[*arc] != root)
+            .map(|arc| (self.from[arc], self.to[arc] - self.rows, self.flow[arc] as u32)).collect()
+    }
+}
+
+// Every row and column a single unit: the cheapest of the largest plans, like 'min_cost_max_matching'
+pub fn ns_assignment(graph: &Graph) -> (Assignment, usize) {
+    let mut ns = NetworkSimplex::new(graph, &vec![1; graph.rows], &vec![1; graph.cols]);
+    ns.solve();
+    let mut row_to_col: Vec<Option<usize>> = vec![None; graph.rows];
+    for (s, d, _) in ns.flows() {
+        row_to_col[s] = Some(d);
+    }
+    (Assignment::new(row_to_col, graph.cols, graph), ns.pivots)
+}
+
+// Transportation problem on the cost matrix, forbidden pairs left out: shipments (supply x demand) and their cost
+pub fn ns_transport(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, supply: &[u32], demand: &[u32]) -> (Vec<Vec<u32>>, u64) {
+    let graph = Graph::from_matrix(cost_arr, forb, supply.len(), demand.len());
+    let mut ns = NetworkSimplex::new(&graph, supply, demand);
+    ns.solve();
+    let mut flow: Vec<Vec<u32>> = vec![vec![0; demand.len()]; supply.len()];
+    let mut sum_cost: u64 = 0;
+    for (s, d, q) in ns.flows() {
+        flow[s][d] = q;
+        sum_cost += q as u64 * cost_arr[s][d] as u64;
+    }
+    (flow, sum_cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::matching::{all_plan_costs, Graph};
+    use crate::{munkres2, test_cost_arr, test_instance};
+    use super::ns_assignment;
+
+    #[test]
+    fn ns_assignment_matches_munkres2() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(2);
+        for size in [1, 2, 5, 10, 30] {
+            for max in [1, 100, 5000] { // many ties and degenerate pivots with max 1
+                let forb = test_instance(&mut cost_arr, &mut rng, size, size, max, 0);
+                let (plan, _) = ns_assignment(&Graph::from_matrix(&cost_arr, &forb, size, size));
+                assert_eq!(plan.size(), size);
+                assert_eq!(plan.cost, munkres2(&cost_arr, &forb, size, size).0 as u64, "{}x{}, costs 0..={}", size, size, max);
+            }
+        }
+    }
+
+    // the cheapest of the largest plans when not every row or column can be matched
+    #[test]
+    fn ns_assignment_rectangular_and_sparse() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(3);
+        for (s_size, d_size, forbidden_pct) in [(3, 6, 0), (6, 3, 0), (5, 5, 50), (4, 6, 60), (6, 4, 40)] {
+            for _ in 0..5 {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 50, forbidden_pct);
+                let graph = Graph::from_matrix(&cost_arr, &forb, s_size, d_size);
+                let (plan, _) = ns_assignment(&graph);
+                assert_eq!(Some(plan.cost), all_plan_costs(&graph).first().copied(), "{}x{}", s_size, d_size);
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::cmp;
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::console;
+use crate::experiment;
+use crate::forbidden::Forbidden;
+use crate::stats::median;
+use crate::{environment, init_cost, instance_class, new_instance, print_summary, results, run_timed, CostRange, Samples, Solvers, DSIZE,
+    SOLVERS, SSIZE, WARMUP_ITER};
+
+// The experiments of kuhn-hungarian-perf.pdf from one command ("report"): its tables go to REPORT_FILE,
+// a chart of time against size per cost range to CHART_PREFIX<series>_<n>.svg and every run to REPORT_RESULTS
+const REPORT_ITER: usize = 5; // instances per scenario, the numbers of the PDF are over 5
+const REPORT_FILE: &str = "report.md";
+const REPORT_RESULTS: &str = "report.csv"; // like results.csv, so two reports can be compared with regress
+const CHART_PREFIX: &str = "report_";
+
+const NARROW: CostRange = CostRange { min: 0, max: 30, zero_pct: 0, forbidden_pct: 0 };
+const WIDE: CostRange = CostRange { min: 10, max: 1800, zero_pct: 0, forbidden_pct: 0 };
+const fn sparse(zero_pct: u32) -> CostRange {
+    CostRange { min: 1, max: 30, zero_pct, forbidden_pct: 0 }
+}
+
+struct Series {
+    name: &'static str,
+    sizes: &'static [(usize, usize)], // supply x demand
+    ranges: &'static [CostRange],
+    chart: bool, // time against size, one chart per cost range
+}
+
+const SUITE: [Series; 3] = [
+    // Table 2
+    Series { name: "shapes", sizes: &[(2000, 2000), (1000, 2000), (500, 8000)], ranges: &[NARROW, WIDE], chart: false },
+    // Table 3
+    Series { name: "1000xn", sizes: &[(1000, 1000), (1000, 2000), (1000, 4000), (1000, 8000), (1000, 16000), (1000, 24000), (1000, 32000)],
+             ranges: &[sparse(50), NARROW, WIDE], chart: true },
+    // Figure 1
+    Series { name: "sparsity", sizes: &[(1000, 16000)], ranges: &[NARROW, sparse(25), sparse(50), sparse(75), sparse(90)], chart: false },
+];
+
+// What is run in every scenario: the solvers on 'warmup' instances not counted, then on 'instances'
+// instances 'repeats' times each, in a new random order every time
+pub struct Plan<'a> {
+    pub solvers: &'a [Solvers],
+    pub timeouts: &'a [(Solvers, Duration)], // the others run to the end
+    pub isolated: bool, // see isolation.rs, the timeout of an in-crate solver needs it
+    pub warmup: usize,
+    pub instances: usize,
+    pub repeats: usize,
+}
+
+impl Plan<'_> {
+    // timed runs of that many scenarios, for the progress display
+    pub fn runs(&self, scenarios: usize) -> usize {
+        scenarios * (self.warmup + self.instances) * self.repeats * self.solvers.len()
+    }
+}
+
+// the solvers of the PDF, greedy LCM is LCM2 now; the network simplex stands in for GLPK at these sizes
+const REPORT_SOLVERS: [Solvers; 6] = [Solvers::RUST2, Solvers::NetSimplex, Solvers::PYTHON3, Solvers::PYTHON5, Solvers::CPP3, Solvers::LCM2];
+// a run of the 1000x32000 scenarios may take minutes, one that takes longer is killed and flagged
+const REPORT_TIMEOUTS: [(Solvers, Duration); 6] = [
+    (Solvers::RUST2, Duration::from_secs(600)),
+    (Solvers::NetSimplex, Duration::from_secs(600)),
+    (Solvers::PYTHON3, Duration::from_secs(300)),
+    (Solvers::PYTHON5, Duration::from_secs(300)),
+    (Solvers::CPP3, Duration::from_secs(300)),
+    (Solvers::LCM2, Duration::from_secs(60)),
+];
+
+const PLAN: Plan = Plan { solvers: &REPORT_SOLVERS, timeouts: &REPORT_TIMEOUTS, isolated: true, warmup: WARMUP_ITER,
+    instances: REPORT_ITER, repeats: 1 };
+
+// what the runs of a solver in a scenario (series, size and cost range) gave
+pub struct Cell {
+    median: f64,
+    invalid: usize,
+    suboptimal: usize,
+}
+
+pub fn report(cost_arr: &mut [[u16; DSIZE]; SSIZE]) {
+    let start = Instant::now();
+    results::open(REPORT_RESULTS, String::new());
+    console::start(PLAN.runs(SUITE.iter().map(|s| s.sizes.len() * s.ranges.len()).sum()));
+    let mut md = header("Assignment solvers", "report", &PLAN);
+    let mut rng = rand::thread_rng();
+    for series in SUITE.iter() {
+        let cells: Vec<Vec<Vec<Option<Cell>>>> = series.ranges.iter() // by cost range, size, solver of the plan
+            .map(|costs| series.sizes.iter().map(|(s, d)| run_scenario(cost_arr, *s, *d, costs, &PLAN, &mut rng)).collect())
+            .collect();
+        md += &table(series.name, series.sizes, series.ranges, PLAN.solvers, &cells);
+        if series.chart {
+            for (r, costs) in series.ranges.iter().enumerate() {
+                let filename = format!("{}{}_{}.svg", CHART_PREFIX, series.name, r + 1);
+                chart(&filename, &format!("{}, costs {}", series.name, costs), series.sizes, PLAN.solvers, &cells[r]);
+                md += &format!("\n![{}, costs {}]({})\n", series.name, costs, filename);
+            }
+        }
+        // written after every series, a long suite that fails still leaves the ones before
+        let mut writer = File::create(REPORT_FILE).expect("creation failed");
+        write!(&mut writer, "{}", md).unwrap();
+    }
+    console::finish();
+    println!("Report written to {} after {} s", REPORT_FILE, start.elapsed().as_secs());
+}
+
+// The top of a report: where and on what it was made, and how to read the tables
+pub fn header(title: &str, command: &str, plan: &Plan) -> String {
+    let mut ret = format!("# {}\n\nGenerated {} by `cargo run --release -- {}` in {}\n\n```\n{}```\n\n", title,
+        Utc::now().format("%Y-%m-%d %H:%M"), command, std::env::current_dir().map_or(String::new(), |d| d.display().to_string()),
+        environment::describe());
+    ret += &format!("Median time in ms of {} instance(s) per scenario, {} run(s) each, after {} warm-up instance(s). [123]: a run \
+        of the solver gave a plan costlier than the optimum, 123!: a run gave an invalid or infeasible plan or was killed at its timeout, \
+        -: not run.\n", plan.instances, plan.repeats, plan.warmup);
+    if !plan.timeouts.is_empty() {
+        let timeouts: Vec<String> = plan.timeouts.iter()
+            .map(|(key, timeout)| format!("{} {} s", <&'static str>::from(key.clone()), timeout.as_secs_f64()))
+            .collect();
+        ret += &format!("\nTimeouts: {}.\n", timeouts.join(", "));
+    }
+    ret
+}
+
+// Runs the plan on instances of one size and cost range, drawn with 'rng'; a cell per solver of the plan
+pub fn run_scenario(cost_arr: &mut [[u16; DSIZE]; SSIZE], s_size: usize, d_size: usize, costs: &CostRange, plan: &Plan,
+                    rng: &mut impl Rng) -> Vec<Option<Cell>> {
+    experiment::set_runs(plan.isolated, plan.timeouts);
+    let max_size = cmp::max(s_size, d_size);
+    init_cost(cost_arr, max_size); // the padding of a rectangular instance, a larger one before may have left costs there
+    let mut forb = Forbidden::new(max_size, max_size);
+    let mut samples = Samples::new();
+    let mut flagged = [(0, 0); SOLVERS]; // invalid and suboptimal runs
+    let mut order: Vec<Solvers> = plan.solvers.to_vec();
+    let mut shuffle = rand::thread_rng(); // not 'rng', the instances of a seed stay the same whatever the solvers
+    results::set_class(instance_class(s_size, d_size, costs));
+    for iter in 0..plan.warmup + plan.instances {
+        console::stage(format!("{}x{}, costs {}: instance {}{}", s_size, d_size, costs, iter, if iter < plan.warmup { " (warm-up)" } else { "" }));
+        results::set_instance(iter.checked_sub(plan.warmup));
+        if iter == plan.warmup {
+            samples.clear();
+        }
+        let munk_cost = new_instance(cost_arr, &mut forb, s_size, d_size, costs, rng);
+        for _ in 0..plan.repeats {
+            order.shuffle(&mut shuffle);
+            for key in order.iter() {
+                let (invalid, suboptimal) = run_timed(key, munk_cost, d_size, s_size, cost_arr, &forb, &mut samples);
+                if iter >= plan.warmup {
+                    flagged[key.clone() as usize].0 += invalid as usize;
+                    flagged[key.clone() as usize].1 += suboptimal as usize;
+                }
+            }
+        }
+    }
+    print_summary(&samples, plan.solvers, true);
+    plan.solvers.iter().map(|key| {
+        let idx = key.clone() as usize;
+        if samples.time[idx].is_empty() {
+            return None;
+        }
+        Some(Cell { median: median(&samples.time[idx]), invalid: flagged[idx].0, suboptimal: flagged[idx].1 })
+    }).collect()
+}
+
+fn cell_text(cell: &Option<Cell>) -> String {
+    let Some(c) = cell else {
+        return "-".to_string();
+    };
+    let time = if c.suboptimal > 0 { format!("[{}]", c.median) } else { c.median.to_string() };
+    if c.invalid > 0 { format!("{}!", time) } else { time }
+}
+
+// the solvers (by their index in the plan) that ran in any of the scenarios
+fn columns(rows: &[&[Option<Cell>]]) -> Vec<usize> {
+    (0..rows.first().map_or(0, |r| r.len())).filter(|i| rows.iter().any(|solvers| solvers[*i].is_some())).collect()
+}
+
+// A row per size and cost range, a column per solver; cells by cost range, size and solver
+pub fn table(name: &str, sizes: &[(usize, usize)], ranges: &[CostRange], solvers: &[Solvers], cells: &[Vec<Vec<Option<Cell>>>]) -> String {
+    let columns = columns(&cells.iter().flatten().map(|r| r.as_slice()).collect::<Vec<_>>());
+    let names: Vec<&'static str> = columns.iter().map(|i| solvers[*i].clone().into()).collect();
+    let mut ret = format!("\n## {}\n\n| Size | Costs | {} |\n|---|---|{}\n", name, names.join(" | "), "---:|".repeat(names.len()));
+    for (i, (s_size, d_size)) in sizes.iter().enumerate() {
+        for (r, costs) in ranges.iter().enumerate() {
+            let row: Vec<String> = columns.iter().map(|c| cell_text(&cells[r][i][*c])).collect();
+            ret += &format!("| {}x{} | {} | {} |\n", s_size, d_size, costs, row.join(" | "));
+        }
+    }
+    ret
+}
+
+const WIDTH: f64 = 760.0;
+const HEIGHT: f64 = 460.0;
+const LEFT: f64 = 70.0; // margins around the plot, the legend is on the right
+const RIGHT: f64 = 170.0;
+const TOP: f64 = 40.0;
+const BOTTOM: f64 = 70.0;
+const COLOURS: [&str; 8] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf"];
+
+// Time against the number of cells (supply x demand) of every solver, both on log scales, a solid or
+// dashed line per solver. Suboptimal medians are drawn hollow, invalid ones as a cross. Times below 1 ms are drawn at 1 ms.
+pub fn chart(filename: &str, title: &str, sizes: &[(usize, usize)], solvers: &[Solvers], cells: &[Vec<Option<Cell>>]) {
+    let columns = columns(&cells.iter().map(|r| r.as_slice()).collect::<Vec<_>>());
+    let xs: Vec<f64> = sizes.iter().map(|(s, d)| ((s * d) as f64).log10()).collect();
+    let (x_min, x_max) = (xs.iter().cloned().fold(f64::MAX, f64::min), xs.iter().cloned().fold(f64::MIN, f64::max));
+    let times: Vec<f64> = cells.iter().flatten().flatten().map(|c| c.median.max(1.0).log10()).collect();
+    let y_min = times.iter().cloned().fold(f64::MAX, f64::min).floor();
+    let y_max = times.iter().cloned().fold(f64::MIN, f64::max).ceil().max(y_min + 1.0);
+    let (plot_w, plot_h) = (WIDTH - LEFT - RIGHT, HEIGHT - TOP - BOTTOM);
+    let x = |v: f64| LEFT + if x_max > x_min { (v - x_min) / (x_max - x_min) * plot_w } else { plot_w / 2.0 };
+    let y = |v: f64| TOP + plot_h - (v - y_min) / (y_max - y_min) * plot_h;
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"11\">\n",
+        WIDTH, HEIGHT);
+    svg += &format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", WIDTH, HEIGHT);
+    svg += &format!("<text x=\"{}\" y=\"20\" font-size=\"14\">{}</text>\n", LEFT, title);
+    for p in y_min as i32..=y_max as i32 {
+        let py = y(p as f64);
+        svg += &format!("<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#ddd\"/>\n", LEFT, py, LEFT + plot_w, py);
+        svg += &format!("<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\n", LEFT - 6.0, py + 4.0, 10u64.pow(p as u32));
+    }
+    for ((s_size, d_size), v) in sizes.iter().zip(xs.iter()) {
+        let px = x(*v);
+        svg += &format!("<line x1=\"{:.1}\" y1=\"{}\" x2=\"{:.1}\" y2=\"{}\" stroke=\"#ddd\"/>\n", px, TOP, px, TOP + plot_h);
+        svg += &format!("<text x=\"{:.1}\" y=\"{}\" text-anchor=\"end\" transform=\"rotate(-30 {:.1} {})\">{}x{}</text>\n",
+            px, TOP + plot_h + 14.0, px, TOP + plot_h + 14.0, s_size, d_size);
+    }
+    svg += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>\n", LEFT, TOP, plot_w, plot_h);
+    svg += &format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">supply x demand</text>\n", LEFT + plot_w / 2.0, HEIGHT - 8.0);
+    svg += &format!("<text x=\"16\" y=\"{}\" text-anchor=\"middle\" transform=\"rotate(-90 16 {})\">median ms</text>\n",
+        TOP + plot_h / 2.0, TOP + plot_h / 2.0);
+
+    for (n, col) in columns.iter().enumerate() {
+        let colour = COLOURS[n % COLOURS.len()];
+        let dash = if n < COLOURS.len() { "" } else { " stroke-dasharray=\"5,3\"" };
+        let points: Vec<(f64, f64, &Cell)> = cells.iter().zip(xs.iter())
+            .filter_map(|(solvers, v)| solvers[*col].as_ref().map(|c| (x(*v), y(c.median.max(1.0).log10()), c))).collect();
+        let path: Vec<String> = points.iter().map(|(px, py, _)| format!("{:.1},{:.1}", px, py)).collect();
+        svg += &format!("<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"{}/>\n", path.join(" "), colour, dash);
+        for (px, py, c) in points.iter() {
+            svg += &marker(*px, *py, c, colour);
+        }
+        let name: &'static str = solvers[*col].clone().into();
+        let ly = TOP + 6.0 + n as f64 * 16.0;
+        svg += &format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"1.5\"{}/>\n",
+            WIDTH - RIGHT + 14.0, ly, WIDTH - RIGHT + 34.0, ly, colour, dash);
+        svg += &format!("<text x=\"{}\" y=\"{}\">{}</text>\n", WIDTH - RIGHT + 40.0, ly + 4.0, name);
+    }
+    let ly = TOP + 16.0 + columns.len() as f64 * 16.0;
+    svg += &marker(WIDTH - RIGHT + 24.0, ly, &Cell { median: 0.0, invalid: 0, suboptimal: 1 }, "black");
+    svg += &format!("<text x=\"{}\" y=\"{}\">suboptimal</text>\n", WIDTH - RIGHT + 40.0, ly + 4.0);
+    svg += &marker(WIDTH - RIGHT + 24.0, ly + 16.0, &Cell { median: 0.0, invalid: 1, suboptimal: 0 }, "black");
+    svg += &format!("<text x=\"{}\" y=\"{}\">invalid</text>\n", WIDTH - RIGHT + 40.0, ly + 20.0);
+    svg += "</svg>\n";
+    let mut writer = File::create(filename).expect("creation failed");
+    write!(&mut writer, "{}", svg).unwrap();
+}
+
+// a dot, hollow when a run was suboptimal, a cross when one was invalid
+fn marker(px: f64, py: f64, cell: &Cell, colour: &str) -> String {
+    if cell.invalid > 0 {
+        format!("<path d=\"M{:.1},{:.1} l8,8 m0,-8 l-8,8\" stroke=\"{}\" stroke-width=\"2\"/>\n", px - 4.0, py - 4.0, colour)
+    } else if cell.suboptimal > 0 {
+        format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"4\" fill=\"white\" stroke=\"{}\" stroke-width=\"1.5\"/>\n", px, py, colour)
+    } else {
+        format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"/>\n", px, py, colour)
+    }
+}
@@ -0,0 +1,216 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::Mutex;
+use crate::console::note;
+use crate::stats::{holm, mann_whitney, median};
+use crate::Solvers;
+
+// Every timed run of the main loop (and the adaptive mode) goes into a results file, one line each,
+// so that a later run can be checked against it (see regress): copy a results file to keep it as a baseline.
+// An instance class is what the instances of a run are drawn from: sizes, cost range and forbidden share.
+const HEADER: &str = "solver,class,instance,time_ms,cost,expected,invalid,suboptimal";
+const ALPHA: f64 = 0.05; // significance of a slowdown, after Holm's adjustment over all solver/class pairs
+const SLOWDOWN_PCT: f64 = 10.0; // smaller slowdowns of the median are not flagged however significant
+
+struct Output {
+    writer: BufWriter<File>,
+    class: String,
+    instance: Option<usize>, // None in warm-up runs, which are not written
+}
+
+static OUTPUT: Mutex<Option<Output>> = Mutex::new(None);
+
+thread_local! {
+    // what went wrong in the current run: (invalid or infeasible plan, cost above the expected one)
+    static FLAGS: Cell<(bool, bool)> = const { Cell::new((false, false)) };
+}
+
+// Prints a problem with a plan and marks the run invalid
+pub fn invalid(msg: String) {
+    note(msg);
+    FLAGS.with(|f| f.set((true, f.get().1)));
+}
+
+// Prints that an exact solver missed the optimum and marks the run suboptimal
+pub fn suboptimal(msg: String) {
+    note(msg);
+    FLAGS.with(|f| f.set((f.get().0, true)));
+}
+
+pub fn take_flags() -> (bool, bool) {
+    FLAGS.with(|f| f.replace((false, false)))
+}
+
+// flags of a run in another process (see isolation.rs)
+pub fn set_flags(flags: (bool, bool)) {
+    FLAGS.with(|f| f.set(flags));
+}
+
+pub fn open(filename: &str, class: String) {
+    let mut writer = BufWriter::new(File::create(filename).expect("creation failed"));
+    writeln!(&mut writer, "{}", HEADER).unwrap();
+    *OUTPUT.lock().unwrap() = Some(Output { writer, class, instance: None });
+}
+
+// runs of several classes in one file, as in a report (see report.rs)
+pub fn set_class(class: String) {
+    if let Some(out) = OUTPUT.lock().unwrap().as_mut() {
+        out.class = class;
+    }
+}
+
+pub fn set_instance(instance: Option<usize>) {
+    if let Some(out) = OUTPUT.lock().unwrap().as_mut() {
+        out.instance = instance;
+    }
+}
+
+pub fn record(key: &Solvers, time: u128, cost: Option<u32>, expected: u32, (invalid, suboptimal): (bool, bool)) {
+    let mut output = OUTPUT.lock().unwrap();
+    let Some(out) = output.as_mut() else {
+        return;
+    };
+    let Some(instance) = out.instance else {
+        return;
+    };
+    let name: &'static str = key.clone().into();
+    writeln!(&mut out.writer, "{},{},{},{},{},{},{},{}", name, quoted(&out.class), instance, time,
+        cost.map_or(String::new(), |c| c.to_string()), expected, invalid, suboptimal).unwrap();
+    out.writer.flush().unwrap();
+}
+
+// a CSV field, in quotes if it has a comma or quote in it (class labels of sparse costs do)
+fn quoted(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// the fields of a CSV line, quotes taken off
+fn split_fields(line: &str) -> Vec<String> {
+    let mut ret: Vec<String> = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                ret.last_mut().unwrap().push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => ret.push(String::new()),
+            _ => ret.last_mut().unwrap().push(c),
+        }
+    }
+    ret
+}
+
+struct Run {
+    time: u128,
+    invalid: bool,
+    suboptimal: bool,
+}
+
+// (solver, class) -> runs, None if the file cannot be read
+fn read(filename: &str) -> Option<BTreeMap<(String, String), Vec<Run>>> {
+    let mut ret: BTreeMap<(String, String), Vec<Run>> = BTreeMap::new();
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("{}: {}", filename, e);
+            return None;
+        }
+    };
+    for line in BufReader::new(file).lines().map_while(Result::ok).skip(1) {
+        let fields = split_fields(&line);
+        if fields.len() != 8 {
+            println!("{}: skipped line '{}'", filename, line);
+            continue;
+        }
+        let Ok(time) = fields[3].parse() else {
+            println!("{}: skipped line '{}'", filename, line);
+            continue;
+        };
+        ret.entry((fields[0].clone(), fields[1].clone())).or_default()
+            .push(Run { time, invalid: fields[6] == "true", suboptimal: fields[7] == "true" });
+    }
+    Some(ret)
+}
+
+// Compares a results file with a baseline one, solver by solver and class by class: flags significant
+// slowdowns, larger shares of invalid or suboptimal runs than in the baseline and solvers or classes that
+// are gone (crashed or left out). Some(true) if anything was flagged, None if a file cannot be read.
+pub fn regress(baseline: &str, current: &str) -> Option<bool> {
+    let base = read(baseline)?;
+    let new = read(current)?;
+    let mut slowdowns: Vec<(&(String, String), f64, f64, f64)> = vec![]; // key, median before, after, p
+    let mut flagged = false;
+    for (key, runs) in &new {
+        let Some(before) = base.get(key) else {
+            println!("{} {}: no baseline", key.0, key.1);
+            continue;
+        };
+        let times: Vec<u128> = runs.iter().map(|r| r.time).collect();
+        let base_times: Vec<u128> = before.iter().map(|r| r.time).collect();
+        let c = mann_whitney(&times, &base_times);
+        slowdowns.push((key, median(&base_times), median(&times), if c.effect > 0.0 { c.p } else { 1.0 }));
+        for (what, now, was) in [("invalid", runs.iter().filter(|r| r.invalid).count(), before.iter().filter(|r| r.invalid).count()),
+            ("suboptimal", runs.iter().filter(|r| r.suboptimal).count(), before.iter().filter(|r| r.suboptimal).count())] {
+            if now as f64 / runs.len() as f64 > was as f64 / before.len() as f64 {
+                println!("{} {}: REGRESSION, {} of {} runs {} (baseline {} of {})", key.0, key.1, now, runs.len(), what, was, before.len());
+                flagged = true;
+            }
+        }
+    }
+    for key in base.keys().filter(|k| !new.contains_key(*k)) {
+        println!("{} {}: REGRESSION, not in {}", key.0, key.1, current);
+        flagged = true;
+    }
+    let adjusted = holm(&slowdowns.iter().map(|(_, _, _, p)| *p).collect::<Vec<f64>>());
+    for ((key, before, after, _), p) in slowdowns.iter().zip(adjusted.iter()) {
+        let change = (after - before) * 100.0 / before.max(1.0);
+        let slower = *p < ALPHA && change > SLOWDOWN_PCT;
+        println!("{} {}: median {} -> {} ms ({:+.1}%), p {:.3}{}", key.0, key.1, before, after, change, p,
+            if slower { ", REGRESSION: slower" } else { "" });
+        flagged |= slower;
+    }
+    Some(flagged)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_file;
+    use crate::Solvers;
+    use super::{open, read, record, set_class, set_instance};
+
+    // a sparse class has a comma in its label, its runs must come back under that label
+    #[test]
+    fn recorded_runs_read_back() {
+        let filename = std::env::temp_dir().join(format!("results_{}.csv", std::process::id())).display().to_string();
+        let classes = ["5x5 1..30 0% forbidden", "5x5 50% 0, 1..30 5% forbidden", "a \"quoted\", label"];
+        for (i, class) in classes.iter().enumerate() {
+            if i == 0 {
+                open(&filename, class.to_string());
+            } else {
+                set_class(class.to_string());
+            }
+            set_instance(Some(i));
+            record(&Solvers::RUST2, 10 + i as u128, Some(7), 7, (false, i == 2));
+            record(&Solvers::Auction, 20, None, 7, (true, false));
+        }
+        set_instance(None);
+        let runs = read(&filename).unwrap();
+        remove_file(&filename).unwrap();
+        assert_eq!(runs.len(), 2 * classes.len());
+        for (i, class) in classes.iter().enumerate() {
+            let rust2 = &runs[&("RUST2".to_string(), class.to_string())];
+            assert_eq!((rust2.len(), rust2[0].time, rust2[0].invalid, rust2[0].suboptimal), (1, 10 + i as u128, false, i == 2), "{}", class);
+            let auction = &runs[&("AUCTION".to_string(), class.to_string())];
+            assert_eq!((auction.len(), auction[0].time, auction[0].invalid), (1, 20, true), "{}", class);
+        }
+    }
+}
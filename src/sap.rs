@@ -0,0 +1,327 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use crate::matching::Graph;
+
+// Successive shortest augmenting paths (Dijkstra with potentials) on the flow network
+// source -> free rows -> columns -> free columns -> sink. After k augmentations the matching
+// is the cheapest one with k pairs, which is what max-cardinality mode needs when forbidden
+// pairs do not allow a full plan. Potentials are node prices in the residual network, reduced
+// cost of a row->column edge is cost + row_pot - col_pot and never negative.
+#[derive(Clone)]
+pub struct Sap {
+    pub restr: Option<Restrictions>,
+    pub row_pot: Vec<i64>,
+    pub col_pot: Vec<i64>,
+    src_pot: i64,
+    sink_pot: i64,
+    pub row_match: Vec<Option<usize>>,
+    pub col_match: Vec<Option<usize>>,
+    match_cost: Vec<i64>, // cost of the pair a row is in, the reverse edge costs minus that
+}
+
+impl Sap {
+    pub fn new(rows: usize, cols: usize) -> Sap {
+        Sap {
+            restr: None,
+            row_pot: vec![0; rows],
+            col_pot: vec![0; cols],
+            src_pot: 0,
+            sink_pot: 0,
+            row_match: vec![None; rows],
+            col_match: vec![None; cols],
+            match_cost: vec![0; rows],
+        }
+    }
+
+    fn allows(&self, s: usize, d: usize) -> bool {
+        match &self.restr {
+            None => true,
+            Some(r) => r.allows(s, d),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.row_match.iter().filter(|d| d.is_some()).count()
+    }
+
+    // Cheap start for the case when every row (or every column) is known to end up matched:
+    // reduce rows (columns) by their minimum and pair up along zero reduced costs.
+    // Source (sink) edges would not stay optimal if some rows (columns) stayed free, hence the condition.
+    pub fn reduce_and_match(&mut self, graph: &Graph, all_rows_matched: bool, all_cols_matched: bool) {
+        if all_rows_matched {
+            for s in 0..graph.rows {
+                let min = graph.adj[s].iter().filter(|(d, _)| self.allows(s, *d)).map(|(_, c)| *c as i64).min().unwrap_or(0);
+                self.row_pot[s] = -min;
+            }
+            self.src_pot = self.row_pot.iter().copied().max().unwrap_or(0);
+            for s in 0..graph.rows {
+                for (d, c) in &graph.adj[s] {
+                    if self.col_match[*d].is_none() && self.allows(s, *d) && *c as i64 + self.row_pot[s] - self.col_pot[*d] == 0 {
+                        self.pair(s, *d, *c);
+                        break;
+                    }
+                }
+            }
+        } else if all_cols_matched {
+            for d in 0..graph.cols {
+                self.col_pot[d] = i64::MAX;
+            }
+            for s in 0..graph.rows {
+                for (d, c) in &graph.adj[s] {
+                    if self.allows(s, *d) {
+                        self.col_pot[*d] = self.col_pot[*d].min(*c as i64);
+                    }
+                }
+            }
+            for d in 0..graph.cols {
+                if self.col_pot[d] == i64::MAX {
+                    self.col_pot[d] = 0;
+                }
+            }
+            self.sink_pot = self.col_pot.iter().copied().min().unwrap_or(0);
+            for s in 0..graph.rows {
+                for (d, c) in &graph.adj[s] {
+                    if self.col_match[*d].is_none() && self.allows(s, *d) && *c as i64 + self.row_pot[s] - self.col_pot[*d] == 0 {
+                        self.pair(s, *d, *c);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // Start from column potentials of an earlier, similar instance instead of zeros. Rows get the lowest
+    // potential that keeps all their edges non-negative, for given columns that is the best dual there is.
+    // Then pairs are made along tight edges, those of 'hint' (a previous plan) first, the rest greedily.
+    // Rows (columns) that will not all be matched also need the source (sink) edges to stay optimal:
+    // matched rows may not be priced below free ones, matched columns not above free ones;
+    // pairs that break this are given up, the augmentations will make them again if they are right.
+    pub fn warm_start(&mut self, graph: &Graph, col_pot: Vec<i64>, hint: Option<&[Option<usize>]>,
+                      all_rows_matched: bool, all_cols_matched: bool) {
+        self.col_pot = col_pot;
+        for s in 0..graph.rows {
+            let hint_col = hint.and_then(|h| h.get(s).copied().flatten());
+            let mut pot = i64::MIN;
+            let mut hint_edge: Option<(usize, u32)> = None;
+            for (d, c) in &graph.adj[s] {
+                if self.allows(s, *d) {
+                    pot = pot.max(self.col_pot[*d] - *c as i64);
+                    if hint_col == Some(*d) {
+                        hint_edge = Some((*d, *c));
+                    }
+                }
+            }
+            self.row_pot[s] = if pot == i64::MIN { 0 } else { pot };
+            if let Some((d, c)) = hint_edge {
+                if self.col_match[d].is_none() && c as i64 + self.row_pot[s] - self.col_pot[d] == 0 {
+                    self.pair(s, d, c);
+                }
+            }
+        }
+        for s in 0..graph.rows {
+            if self.row_match[s].is_some() {
+                continue;
+            }
+            for (d, c) in &graph.adj[s] {
+                if self.col_match[*d].is_none() && self.allows(s, *d) && *c as i64 + self.row_pot[s] - self.col_pot[*d] == 0 {
+                    self.pair(s, *d, *c);
+                    break;
+                }
+            }
+        }
+        // giving up a pair frees a row and a column, which may move the other limit, hence the loop
+        loop {
+            let mut changed = false;
+            if !all_rows_matched {
+                let free_rows = self.row_match.iter().zip(self.row_pot.iter()).filter(|(d, _)| d.is_none());
+                if let Some(limit) = free_rows.map(|(_, p)| *p).max() {
+                    for s in 0..graph.rows {
+                        if self.row_match[s].is_some() && self.row_pot[s] < limit {
+                            self.unmatch_row(s);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !all_cols_matched {
+                let free_cols = self.col_match.iter().zip(self.col_pot.iter()).filter(|(s, _)| s.is_none());
+                if let Some(limit) = free_cols.map(|(_, p)| *p).min() {
+                    for d in 0..graph.cols {
+                        if self.col_match[d].is_some() && self.col_pot[d] > limit {
+                            self.unmatch_col(d);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        self.reprice_ends();
+    }
+
+    // Breaks up the pair of row 's' in a perfect matching of a square graph, so that one augmentation
+    // finds the best plan without it (Murty's children). The row and the column are the only free
+    // ones, source and sink are priced so that their edges cost nothing.
+    pub fn unpair(&mut self, s: usize) {
+        let d = self.row_match[s].unwrap();
+        self.row_match[s] = None;
+        self.col_match[d] = None;
+        self.src_pot = self.row_pot[s];
+        self.sink_pot = self.col_pot[d];
+    }
+
+    // Growing the problem: a new row or column comes in free, at the given potential
+    pub fn add_row(&mut self, pot: i64) {
+        self.row_pot.push(pot);
+        self.row_match.push(None);
+        self.match_cost.push(0);
+    }
+
+    pub fn add_col(&mut self, pot: i64) {
+        self.col_pot.push(pot);
+        self.col_match.push(None);
+    }
+
+    pub fn unmatch_row(&mut self, s: usize) {
+        if let Some(d) = self.row_match[s].take() {
+            self.col_match[d] = None;
+        }
+    }
+
+    pub fn unmatch_col(&mut self, d: usize) {
+        if let Some(s) = self.col_match[d].take() {
+            self.row_match[s] = None;
+        }
+    }
+
+    // Prices source and sink so that edges to free rows and from free columns cost nothing negative.
+    // Their other edges (back from matched rows, into matched columns) are left to the caller:
+    // they do not matter when all rows and all columns end up matched, as in a square graph with a perfect plan.
+    pub fn reprice_ends(&mut self) {
+        let free_rows = self.row_match.iter().zip(self.row_pot.iter()).filter(|(d, _)| d.is_none());
+        self.src_pot = free_rows.map(|(_, p)| *p).max().unwrap_or(0);
+        let free_cols = self.col_match.iter().zip(self.col_pot.iter()).filter(|(s, _)| s.is_none());
+        self.sink_pot = free_cols.map(|(_, p)| *p).min().unwrap_or(0);
+    }
+
+    // sum of the costs of all pairs
+    pub fn cost(&self) -> i64 {
+        self.row_match.iter().zip(self.match_cost.iter()).filter(|(d, _)| d.is_some()).map(|(_, c)| *c).sum()
+    }
+
+    pub fn pair(&mut self, s: usize, d: usize, c: u32) {
+        self.row_match[s] = Some(d);
+        self.col_match[d] = Some(s);
+        self.match_cost[s] = c as i64;
+    }
+
+    // Augments until 'target' pairs are matched or no augmenting path is left
+    pub fn solve(&mut self, graph: &Graph, target: usize) {
+        let mut size = self.size();
+        while size < target && self.augment(graph) {
+            size += 1;
+        }
+    }
+
+    // One cheapest augmenting path from any free row to any free column, false if there is none
+    pub fn augment(&mut self, graph: &Graph) -> bool {
+        let rows = graph.rows;
+        let sink = rows + graph.cols;
+        let mut dist: Vec<i64> = vec![i64::MAX; sink + 1];
+        let mut pred: Vec<usize> = vec![usize::MAX; sink + 1]; // usize::MAX for the source
+        let mut done: Vec<bool> = vec![false; sink + 1];
+        // equal distances go to the higher node: columns and the sink before rows, so that on a
+        // nearly optimal plan with many tight edges the path is found without settling every row
+        let mut heap: BinaryHeap<(Reverse<i64>, usize)> = BinaryHeap::new();
+
+        for (s, d) in self.row_match.iter().enumerate() {
+            if d.is_none() {
+                dist[s] = self.src_pot - self.row_pot[s];
+                heap.push((Reverse(dist[s]), s));
+            }
+        }
+        while let Some((Reverse(dst), node)) = heap.pop() {
+            if done[node] {
+                continue;
+            }
+            done[node] = true;
+            if node == sink {
+                break;
+            }
+            if node < rows {
+                let s = node;
+                for (d, c) in &graph.adj[s] {
+                    if self.row_match[s] == Some(*d) || !self.allows(s, *d) {
+                        continue;
+                    }
+                    let next = rows + d;
+                    let nd = dst + *c as i64 + self.row_pot[s] - self.col_pot[*d];
+                    if nd < dist[next] {
+                        dist[next] = nd;
+                        pred[next] = s;
+                        heap.push((Reverse(nd), next));
+                    }
+                }
+            } else {
+                let d = node - rows;
+                let (next, nd) = match self.col_match[d] {
+                    Some(s) => (s, dst - self.match_cost[s] + self.col_pot[d] - self.row_pot[s]),
+                    None => (sink, dst + self.col_pot[d] - self.sink_pot),
+                };
+                if nd < dist[next] {
+                    dist[next] = nd;
+                    pred[next] = node;
+                    heap.push((Reverse(nd), next));
+                }
+            }
+        }
+        if !done[sink] {
+            return false;
+        }
+        // potentials: nodes not finished by Dijkstra are moved by the distance to the sink
+        let limit = dist[sink];
+        for s in 0..rows {
+            self.row_pot[s] += if done[s] { dist[s] } else { limit };
+        }
+        for d in 0..graph.cols {
+            self.col_pot[d] += if done[rows + d] { dist[rows + d] } else { limit };
+        }
+        self.sink_pot += limit;
+
+        // flip the path, it starts at a free row and ends at a free column
+        let mut col_node = pred[sink];
+        loop {
+            let d = col_node - rows;
+            let s = pred[col_node];
+            let prev = self.row_match[s];
+            self.pair(s, d, graph.adj[s].iter().find(|(col, _)| *col == d).unwrap().1);
+            match prev {
+                Some(_) => col_node = pred[s],
+                None => break,
+            }
+        }
+        true
+    }
+}
+
+// Pairs a solver must use or must not use, for branching schemes like Murty's
+#[derive(Clone)]
+pub struct Restrictions {
+    pub forced: Vec<Option<usize>>, // the only column a row may get
+    pub banned: Vec<Vec<usize>>,    // columns a row may not get, short lists
+}
+
+impl Restrictions {
+    pub fn new(rows: usize) -> Restrictions {
+        Restrictions { forced: vec![None; rows], banned: vec![vec![]; rows] }
+    }
+
+    pub fn allows(&self, s: usize, d: usize) -> bool {
+        match self.forced[s] {
+            Some(f) => f == d,
+            None => !self.banned[s].contains(&d),
+        }
+    }
+}
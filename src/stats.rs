@@ -0,0 +1,197 @@
+// Robust statistics of the timings of one solver
+
+const MAD_LIMIT: f64 = 3.5; // how many (normal-consistent) median absolute deviations make an outlier
+const MAD_NORMAL: f64 = 1.4826; // MAD * this estimates the standard deviation of normal data
+
+pub fn median(values: &[u128]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) as f64 / 2.0
+    } else {
+        sorted[mid] as f64
+    }
+}
+
+// Marks the timings further from the median than MAD_LIMIT deviations. Timings are whole milliseconds,
+// so a MAD of 0 (most runs equal) counts as 1 ms rather than making every other value an outlier.
+pub fn outliers(values: &[u128]) -> Vec<bool> {
+    let med = median(values);
+    let deviations: Vec<u128> = values.iter().map(|v| (*v as f64 - med).abs().round() as u128).collect();
+    let mad = median(&deviations).max(1.0);
+    values.iter().map(|v| (*v as f64 - med).abs() > MAD_LIMIT * MAD_NORMAL * mad).collect()
+}
+
+const Z_95: f64 = 1.96;
+
+// Distribution-free 95% confidence interval of the median: the order statistics around n/2 that
+// a binomial(n, 1/2) count stays between. None with fewer than 8 values, too few for the ranks to exist.
+pub fn median_ci(values: &[u128]) -> Option<(u128, u128)> {
+    let n = values.len() as f64;
+    let half = Z_95 * n.sqrt() / 2.0;
+    let lo = (n / 2.0 - half).floor(); // 1-based ranks
+    let hi = (1.0 + n / 2.0 + half).ceil();
+    if lo < 1.0 || hi > n {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    Some((sorted[lo as usize - 1], sorted[hi as usize - 1]))
+}
+
+// Whether the times of two solvers differ: p is two-sided (normal approximation with the tie and continuity
+// corrections), effect the rank-biserial correlation, -1 when 'a' is always faster, 1 when always slower.
+// Runs of the same instance in the same round are paired (Wilcoxon signed-rank) when the caller says they
+// are, run for run in the same order; others, as in the adaptive mode where solvers get different numbers
+// of runs per instance, are compared as independent samples (Mann-Whitney U).
+pub struct Comparison {
+    pub p: f64,
+    pub effect: f64,
+    pub paired: bool,
+}
+
+pub fn compare(a: &[u128], b: &[u128], paired: bool) -> Comparison {
+    if paired && a.len() == b.len() {
+        wilcoxon(a, b)
+    } else {
+        mann_whitney(a, b)
+    }
+}
+
+fn wilcoxon(a: &[u128], b: &[u128]) -> Comparison {
+    let diffs: Vec<f64> = a.iter().zip(b.iter()).map(|(x, y)| *x as f64 - *y as f64).filter(|d| *d != 0.0).collect();
+    let n = diffs.len() as f64;
+    let (ranks, ties) = ranks(&diffs.iter().map(|d| d.abs()).collect::<Vec<f64>>());
+    let plus: f64 = diffs.iter().zip(ranks.iter()).filter(|(d, _)| **d > 0.0).map(|(_, r)| r).sum();
+    let total = n * (n + 1.0) / 2.0;
+    let var = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - ties / 48.0;
+    Comparison { p: two_sided(plus - total / 2.0, var), effect: if n > 0.0 { (2.0 * plus - total) / total } else { 0.0 }, paired: true }
+}
+
+pub fn mann_whitney(a: &[u128], b: &[u128]) -> Comparison {
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let all: Vec<f64> = a.iter().chain(b.iter()).map(|x| *x as f64).collect();
+    let (ranks, ties) = ranks(&all);
+    let u = ranks[..a.len()].iter().sum::<f64>() - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let var = if n > 1.0 { n1 * n2 / 12.0 * (n + 1.0 - ties / (n * (n - 1.0))) } else { 0.0 };
+    Comparison { p: two_sided(u - n1 * n2 / 2.0, var), effect: if n1 * n2 > 0.0 { 2.0 * u / (n1 * n2) - 1.0 } else { 0.0 }, paired: false }
+}
+
+// average ranks from 1, and the sum of t^3 - t over the groups of t tied values
+fn ranks(values: &[f64]) -> (Vec<f64>, f64) {
+    let mut idx: Vec<usize> = (0..values.len()).collect();
+    idx.sort_by(|i, j| values[*i].total_cmp(&values[*j]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut ties = 0.0;
+    let mut i = 0;
+    while i < idx.len() {
+        let mut j = i;
+        while j + 1 < idx.len() && values[idx[j + 1]] == values[idx[i]] {
+            j += 1;
+        }
+        let t = (j - i + 1) as f64;
+        ties += t * t * t - t;
+        for k in &idx[i..=j] {
+            ranks[*k] = (i + j) as f64 / 2.0 + 1.0;
+        }
+        i = j + 1;
+    }
+    (ranks, ties)
+}
+
+// p of a statistic 'dev' away from its mean under the null hypothesis
+fn two_sided(dev: f64, var: f64) -> f64 {
+    if var <= 0.0 {
+        return 1.0;
+    }
+    let z = (dev.abs() - 0.5).max(0.0) / var.sqrt();
+    erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+// Abramowitz and Stegun 7.1.26, good to about 1e-7
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    poly * (-x * x).exp()
+}
+
+// Holm's step-down adjustment, for the many pairs of a matrix being tested at once
+pub fn holm(ps: &[f64]) -> Vec<f64> {
+    let mut idx: Vec<usize> = (0..ps.len()).collect();
+    idx.sort_by(|i, j| ps[*i].total_cmp(&ps[*j]));
+    let mut ret = vec![0.0; ps.len()];
+    let mut running: f64 = 0.0;
+    for (k, i) in idx.iter().enumerate() {
+        running = running.max((ps[*i] * (ps.len() - k) as f64).min(1.0));
+        ret[*i] = running;
+    }
+    ret
+}
+
+// Reference values as R gives them: wilcox.test(exact = FALSE, correct = TRUE) and p.adjust(method = "holm")
+#[cfg(test)]
+mod tests {
+    use super::{compare, holm, mann_whitney, median_ci, wilcoxon};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn median_ci_ranks() {
+        let mut values: Vec<u128> = (1..=20).rev().collect();
+        assert_eq!(median_ci(&values), Some((5, 16)));
+        values.truncate(8);
+        assert_eq!(median_ci(&values), Some((13, 20)));
+        values.truncate(7);
+        assert_eq!(median_ci(&values), None);
+    }
+
+    #[test]
+    fn wilcoxon_reference() {
+        // all 10 differences positive: V = 55, p = 0.005922
+        let a: Vec<u128> = (11..=20).map(|x| x * 2).collect();
+        let b: Vec<u128> = (11..=20).collect();
+        let c = wilcoxon(&a, &b);
+        assert!(close(c.p, 0.005922), "{}", c.p);
+        assert!(close(c.effect, 1.0));
+        // a zero difference dropped and a tie: V = 6, p = 0.1736
+        let c = wilcoxon(&[5, 5, 5, 5], &[3, 3, 4, 5]);
+        assert!(close(c.p, 0.1736), "{}", c.p);
+    }
+
+    #[test]
+    fn mann_whitney_reference() {
+        // no overlap: W = 0, p = 0.01219
+        let c = mann_whitney(&[1, 2, 3, 4, 5], &[6, 7, 8, 9, 10]);
+        assert!(close(c.p, 0.01219), "{}", c.p);
+        assert!(close(c.effect, -1.0));
+        // ties across the samples: W = 1, p = 0.1573
+        let c = mann_whitney(&[1, 2, 2], &[2, 3, 3]);
+        assert!(close(c.p, 0.1573), "{}", c.p);
+        assert!(close(c.effect, 2.0 / 9.0 - 1.0));
+    }
+
+    #[test]
+    fn compare_paired_only_when_asked() {
+        let (a, b) = ([1, 2, 3, 4, 5], [6, 7, 8, 9, 10]);
+        assert!(compare(&a, &b, true).paired);
+        assert!(!compare(&a, &b, false).paired);
+        assert!(!compare(&a, &b[..4], true).paired);
+    }
+
+    #[test]
+    fn holm_reference() {
+        let adjusted = holm(&[0.01, 0.04, 0.03, 0.005]);
+        for (p, expected) in adjusted.iter().zip([0.03, 0.06, 0.06, 0.02]) {
+            assert!(close(*p, expected), "{:?}", adjusted);
+        }
+        assert_eq!(holm(&[0.5, 0.6]), vec![1.0, 1.0]);
+        assert!(holm(&[]).is_empty());
+    }
+}
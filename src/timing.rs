@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+// Where the time of a solver run goes: phases marked by the run itself (prepare, solve, parse,
+// validate), wall clock, user and system CPU time of this process and of the child it waited for,
+// and what an external solver says it took, which need not include start-up or I/O.
+#[derive(Clone, Default)]
+pub struct Timing {
+    pub wall: u128,
+    pub user: u128,
+    pub sys: u128,
+    pub phases: Vec<(&'static str, u128)>,
+    pub reported: Option<u128>,
+}
+
+const PHASE_NAMES: [&str; 4] = ["prepare", "solve", "parse", "validate"];
+const GAP_MIN_MS: u128 = 100; // below that a difference from the self-reported time is just noise
+const GAP_PCT: u128 = 25;
+
+impl Timing {
+    // one line, for a run in a process of its own to hand back (see isolation.rs)
+    pub fn encode(&self) -> String {
+        let phases: Vec<String> = self.phases.iter().map(|(name, ms)| format!("{}={}", name, ms)).collect();
+        format!("{} {} {} {} {}", self.wall, self.user, self.sys, self.reported.map_or("-".to_string(), |r| r.to_string()),
+            if phases.is_empty() { "-".to_string() } else { phases.join(",") })
+    }
+
+    pub fn decode(line: &str) -> Option<Timing> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            return None;
+        }
+        let mut phases: Vec<(&'static str, u128)> = vec![];
+        for item in fields[4].split(',').filter(|i| *i != "-") {
+            let (name, ms) = item.split_once('=')?;
+            phases.push((PHASE_NAMES.into_iter().find(|n| *n == name)?, ms.parse().ok()?));
+        }
+        Some(Timing { wall: fields[0].parse().ok()?, user: fields[1].parse().ok()?, sys: fields[2].parse().ok()?, phases,
+            reported: if fields[3] == "-" { None } else { Some(fields[3].parse().ok()?) } })
+    }
+}
+
+struct Current {
+    start: Instant,
+    last: Instant,
+    cpu: (Duration, Duration),
+    timing: Timing,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Current>> = const { RefCell::new(None) };
+}
+
+pub fn begin() {
+    let now = Instant::now();
+    let cpu = cpu_self();
+    CURRENT.with(|c| *c.borrow_mut() = Some(Current { start: now, last: now, cpu, timing: Timing::default() }));
+}
+
+pub fn end() -> Timing {
+    let cpu = cpu_self();
+    CURRENT.with(|c| match c.borrow_mut().take() {
+        Some(mut cur) => {
+            cur.timing.wall = cur.start.elapsed().as_millis();
+            cur.timing.user += (cpu.0 - cur.cpu.0).as_millis();
+            cur.timing.sys += (cpu.1 - cur.cpu.1).as_millis();
+            cur.timing
+        }
+        None => Timing::default(),
+    })
+}
+
+// Closes a phase: the time since the last mark (or the start of the run) goes to 'name' and is returned
+pub fn phase(name: &'static str) -> u128 {
+    CURRENT.with(|c| match c.borrow_mut().as_mut() {
+        Some(cur) => {
+            let ms = cur.last.elapsed().as_millis();
+            cur.last = Instant::now();
+            match cur.timing.phases.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, total)) => *total += ms,
+                None => cur.timing.phases.push((name, ms)),
+            }
+            ms
+        }
+        None => 0,
+    })
+}
+
+// CPU time of a child process, from wait4
+pub fn child_cpu(user: Duration, sys: Duration) {
+    CURRENT.with(|c| {
+        if let Some(cur) = c.borrow_mut().as_mut() {
+            cur.timing.user += user.as_millis();
+            cur.timing.sys += sys.as_millis();
+        }
+    });
+}
+
+// The time an external solver wrote into its output, against 'measured' by the parent; large gaps are printed
+pub fn self_reported(cmd: &str, reported: u128, measured: u128) {
+    CURRENT.with(|c| {
+        if let Some(cur) = c.borrow_mut().as_mut() {
+            cur.timing.reported = Some(reported);
+        }
+    });
+    if measured > reported + (reported * GAP_PCT / 100).max(GAP_MIN_MS) {
+        crate::console::info(format!("{}: reports {} ms, took {} ms as seen from here (start-up, imports, I/O)", cmd, reported, measured));
+    }
+}
+
+// Averages over the runs of one solver, one line
+pub fn summary(timings: &[Timing]) -> String {
+    if timings.is_empty() {
+        return String::new();
+    }
+    let avg = |f: &dyn Fn(&Timing) -> u128| timings.iter().map(f).sum::<u128>() as f32 / timings.len() as f32;
+    let mut ret = format!("wall {:.1} ms, user {:.1} ms, sys {:.1} ms", avg(&|t| t.wall), avg(&|t| t.user), avg(&|t| t.sys));
+    let mut names: Vec<&'static str> = vec![];
+    for t in timings {
+        for (name, _) in &t.phases {
+            if !names.contains(name) {
+                names.push(name);
+            }
+        }
+    }
+    for name in names {
+        let ms = avg(&|t| t.phases.iter().find(|(n, _)| *n == name).map_or(0, |(_, ms)| *ms));
+        ret += &format!(", {} {:.1}", name, ms);
+    }
+    if timings.iter().any(|t| t.reported.is_some()) {
+        ret += &format!(", self-reported {:.1}", avg(&|t| t.reported.unwrap_or(0)));
+    }
+    ret
+}
+
+fn cpu_self() -> (Duration, Duration) {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+    (duration(usage.ru_utime), duration(usage.ru_stime))
+}
+
+pub fn duration(tv: libc::timeval) -> Duration {
+    Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+}
@@ -0,0 +1,431 @@
+use std::cmp;
+use std::collections::VecDeque;
+use crate::console::note;
+use crate::forbidden::Forbidden;
+use crate::{BIG_VALUE, DSIZE, SSIZE};
+
+// Capacitated transportation problem: supply row s has supply[s] units, demand column d needs demand[d],
+// a unit on (s, d) costs cost_arr[s][d]. Solved exactly with the transportation simplex (u-v method)
+// from an initial basic feasible solution made by LCM or Vogel.
+// Unbalanced instances get a dummy row (unmet demand) or column (unused supply) for free,
+// forbidden pairs cost big M and a plan that still uses one is infeasible.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialBfs {
+    Lcm,
+    Vogel,
+}
+
+pub struct TransportPlan {
+    pub flow: Vec<Vec<u32>>, // supply x demand, dummies left out
+    pub cost: u64,
+    pub initial_cost: u64, // of the initial basic solution
+    pub iterations: usize,
+    pub unmet_demand: u32,
+    pub unused_supply: u32,
+}
+
+pub fn transport(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, supply: &[u32], demand: &[u32], init: InitialBfs) -> TransportPlan {
+    let (s_size, d_size) = (supply.len(), demand.len());
+    let total_supply: u64 = supply.iter().map(|q| *q as u64).sum();
+    let total_demand: u64 = demand.iter().map(|q| *q as u64).sum();
+    let max_cost = (0..s_size).flat_map(|s| (0..d_size).map(move |d| (s, d)))
+        .filter(|(s, d)| !forb.is_forbidden(*s, *d) && cost_arr[*s][*d] < BIG_VALUE)
+        .map(|(s, d)| cost_arr[s][d] as i64).max().unwrap_or(0);
+    let big = max_cost * cmp::max(total_supply, total_demand) as i64 + 1; // more than any plan
+
+    // balanced instance: a dummy row or column takes the difference
+    let mut rows: Vec<u64> = supply.iter().map(|q| *q as u64).collect();
+    let mut cols: Vec<u64> = demand.iter().map(|q| *q as u64).collect();
+    if total_demand > total_supply {
+        rows.push(total_demand - total_supply);
+    } else if total_supply > total_demand {
+        cols.push(total_supply - total_demand);
+    }
+    let mut c: Vec<Vec<i64>> = vec![vec![0; cols.len()]; rows.len()];
+    for (s, row) in c.iter_mut().enumerate().take(s_size) {
+        for (d, cell) in row.iter_mut().enumerate().take(d_size) {
+            *cell = if forb.is_forbidden(s, d) || cost_arr[s][d] >= BIG_VALUE { big } else { cost_arr[s][d] as i64 };
+        }
+    }
+
+    let mut basis = match init {
+        InitialBfs::Lcm => lcm_bfs(&c, &rows, &cols),
+        InitialBfs::Vogel => vogel_bfs(&c, &rows, &cols),
+    };
+    let initial_cost = basis.cost(&c);
+    let iterations = basis.optimise(&c);
+
+    let mut flow: Vec<Vec<u32>> = vec![vec![0; d_size]; s_size];
+    let mut cost: u64 = 0;
+    for (s, row) in flow.iter_mut().enumerate() {
+        for (d, q) in row.iter_mut().enumerate() {
+            *q = basis.flow[s][d] as u32;
+            cost += basis.flow[s][d] * c[s][d] as u64;
+        }
+    }
+    let unmet_demand = if rows.len() > s_size { basis.flow[s_size].iter().sum::<u64>() as u32 } else { 0 };
+    let unused_supply = if cols.len() > d_size { basis.flow.iter().map(|r| r[d_size]).sum::<u64>() as u32 } else { 0 };
+    TransportPlan { flow, cost, initial_cost, iterations, unmet_demand, unused_supply }
+}
+
+// Checks shipments against quantities and forbidden pairs, prints what is wrong
+pub fn validate(plan: &TransportPlan, forb: &Forbidden, supply: &[u32], demand: &[u32]) -> bool {
+    let mut valid = true;
+    let total = |q: &[u32]| q.iter().map(|x| *x as i64).sum::<i64>(); // a broken plan may ship more than there is
+    let shortage = cmp::max(total(demand) - total(supply), 0);
+    let surplus = cmp::max(total(supply) - total(demand), 0);
+    let shipped_rows: Vec<u32> = plan.flow.iter().map(|row| row.iter().sum()).collect();
+    let shipped_cols: Vec<u32> = (0..demand.len()).map(|d| plan.flow.iter().map(|row| row[d]).sum()).collect();
+    if shipped_rows.iter().zip(supply.iter()).any(|(sh, q)| sh > q) || shipped_cols.iter().zip(demand.iter()).any(|(sh, q)| sh > q) {
+        note("Transport: more shipped than supplied or demanded".to_string());
+        valid = false;
+    }
+    if total(supply) - total(&shipped_rows) != surplus || plan.unused_supply as i64 != surplus
+        || total(demand) - total(&shipped_cols) != shortage || plan.unmet_demand as i64 != shortage {
+        note(format!("Transport: supply and demand do not balance, unused supply {} unmet demand {}", plan.unused_supply, plan.unmet_demand));
+        valid = false;
+    }
+    let forbidden = (0..supply.len()).flat_map(|s| (0..demand.len()).map(move |d| (s, d)))
+        .filter(|(s, d)| plan.flow[*s][*d] > 0 && forb.is_forbidden(*s, *d)).count();
+    if forbidden > 0 {
+        note(format!("Transport: plan is infeasible, {} forbidden pairs used", forbidden));
+        valid = false;
+    }
+    valid
+}
+
+// A basic solution: rows + cols - 1 basic cells forming a spanning tree of rows and columns
+struct Basis {
+    flow: Vec<Vec<u64>>,
+    row_basic: Vec<Vec<usize>>, // basic columns of a row
+    col_basic: Vec<Vec<usize>>, // basic rows of a column
+}
+
+impl Basis {
+    fn new(rows: usize, cols: usize) -> Basis {
+        Basis { flow: vec![vec![0; cols]; rows], row_basic: vec![vec![]; rows], col_basic: vec![vec![]; cols] }
+    }
+
+    fn add(&mut self, s: usize, d: usize, q: u64) {
+        self.flow[s][d] = q;
+        self.row_basic[s].push(d);
+        self.col_basic[d].push(s);
+    }
+
+    fn remove(&mut self, s: usize, d: usize) {
+        self.row_basic[s].retain(|col| *col != d);
+        self.col_basic[d].retain(|row| *row != s);
+    }
+
+    fn cost(&self, c: &[Vec<i64>]) -> u64 {
+        self.flow.iter().zip(c.iter()).map(|(f, c)| f.iter().zip(c.iter()).map(|(q, c)| q * *c as u64).sum::<u64>()).sum()
+    }
+
+    // u-v method: pivots on the most negative reduced cost until there is none, returns the number of pivots.
+    // Assignment-like instances are highly degenerate (pivots shipping 0): after rows + cols degenerate pivots
+    // in a row, Bland's rule (the first negative cell, the first leaving cell in row-major order) takes over
+    // until a pivot ships something again, so the basis cannot cycle.
+    fn optimise(&mut self, c: &[Vec<i64>]) -> usize {
+        let (rows, cols) = (self.row_basic.len(), self.col_basic.len());
+        let mut iterations: usize = 0;
+        let mut degenerate: usize = 0;
+        if rows == 0 || cols == 0 {
+            return iterations;
+        }
+        loop {
+            // potentials from the tree: c[s][d] = u[s] + v[d] on basic cells
+            let mut u: Vec<Option<i64>> = vec![None; rows];
+            let mut v: Vec<Option<i64>> = vec![None; cols];
+            u[0] = Some(0);
+            let mut queue: VecDeque<usize> = VecDeque::from([0]); // rows, cols shifted by 'rows'
+            while let Some(node) = queue.pop_front() {
+                if node < rows {
+                    for d in &self.row_basic[node] {
+                        if v[*d].is_none() {
+                            v[*d] = Some(c[node][*d] - u[node].unwrap());
+                            queue.push_back(rows + d);
+                        }
+                    }
+                } else {
+                    let d = node - rows;
+                    for s in &self.col_basic[d] {
+                        if u[*s].is_none() {
+                            u[*s] = Some(c[*s][d] - v[d].unwrap());
+                            queue.push_back(*s);
+                        }
+                    }
+                }
+            }
+            let (u, v): (Vec<i64>, Vec<i64>) = (u.iter().map(|x| x.unwrap()).collect(), v.iter().map(|x| x.unwrap()).collect());
+
+            let bland = degenerate >= rows + cols;
+            let mut entering: Option<(usize, usize)> = None;
+            let mut best: i64 = 0;
+            'cells: for s in 0..rows {
+                for d in 0..cols {
+                    let reduced = c[s][d] - u[s] - v[d];
+                    if reduced < best {
+                        best = reduced;
+                        entering = Some((s, d));
+                        if bland {
+                            break 'cells;
+                        }
+                    }
+                }
+            }
+            let Some((s, d)) = entering else {
+                return iterations;
+            };
+            iterations += 1;
+
+            // the cycle: the entering cell and the tree path from its column back to its row
+            let path = self.path(s, d);
+            let theta = path.iter().step_by(2).map(|(ps, pd)| self.flow[*ps][*pd]).min().unwrap();
+            let mut tied = path.iter().step_by(2).filter(|(ps, pd)| self.flow[*ps][*pd] == theta);
+            let leaving = *if bland { tied.min() } else { tied.next() }.unwrap();
+            degenerate = if theta == 0 { degenerate + 1 } else { 0 };
+            for (i, (ps, pd)) in path.iter().enumerate() {
+                if i % 2 == 0 {
+                    self.flow[*ps][*pd] -= theta;
+                } else {
+                    self.flow[*ps][*pd] += theta;
+                }
+            }
+            self.remove(leaving.0, leaving.1);
+            self.add(s, d, theta);
+        }
+    }
+
+    // Basic cells on the tree path from column 'd' to row 's', starting at column 'd'
+    fn path(&self, s: usize, d: usize) -> Vec<(usize, usize)> {
+        let rows = self.row_basic.len();
+        let mut pred: Vec<usize> = vec![usize::MAX; rows + self.col_basic.len()];
+        let mut queue: VecDeque<usize> = VecDeque::from([rows + d]);
+        pred[rows + d] = rows + d;
+        while let Some(node) = queue.pop_front() {
+            if node == s {
+                break;
+            }
+            let next: Vec<usize> = if node < rows {
+                self.row_basic[node].iter().map(|col| rows + col).collect()
+            } else {
+                self.col_basic[node - rows].clone()
+            };
+            for n in next {
+                if pred[n] == usize::MAX {
+                    pred[n] = node;
+                    queue.push_back(n);
+                }
+            }
+        }
+        let mut path: Vec<(usize, usize)> = vec![];
+        let mut node = s;
+        while node != rows + d {
+            let prev = pred[node];
+            path.push(if node < rows { (node, prev - rows) } else { (prev, node - rows) });
+            node = prev;
+        }
+        path.reverse(); // from the column of the entering cell, signs go -, +, -, ...
+        path
+    }
+}
+
+// Rows and columns not crossed out yet, with what is left of their quantities
+struct Open {
+    rem_rows: Vec<u64>,
+    rem_cols: Vec<u64>,
+    row_out: Vec<bool>,
+    col_out: Vec<bool>,
+    rows_left: usize,
+    cols_left: usize,
+}
+
+impl Open {
+    fn new(rows: &[u64], cols: &[u64]) -> Open {
+        Open {
+            rem_rows: rows.to_vec(),
+            rem_cols: cols.to_vec(),
+            row_out: vec![false; rows.len()],
+            col_out: vec![false; cols.len()],
+            rows_left: rows.len(),
+            cols_left: cols.len(),
+        }
+    }
+
+    // Every allocation crosses out one line (the row, unless it is the last one), the last one both:
+    // rows + cols - 1 basic cells even when an allocation exhausts a row and a column at once
+    fn allocate(&mut self, basis: &mut Basis, s: usize, d: usize) {
+        let q = cmp::min(self.rem_rows[s], self.rem_cols[d]);
+        basis.add(s, d, q);
+        self.rem_rows[s] -= q;
+        self.rem_cols[d] -= q;
+        if self.rem_rows[s] == 0 && (self.rows_left > 1 || self.cols_left == 1) {
+            self.row_out[s] = true;
+            self.rows_left -= 1;
+            if self.rows_left == 0 {
+                self.col_out[d] = true;
+                self.cols_left -= 1;
+            }
+        } else {
+            self.col_out[d] = true;
+            self.cols_left -= 1;
+        }
+    }
+}
+
+// Least cost method: cells in cost order, ties row-major like 'lcm'
+fn lcm_bfs(c: &[Vec<i64>], rows: &[u64], cols: &[u64]) -> Basis {
+    let mut basis = Basis::new(rows.len(), cols.len());
+    let mut cells: Vec<(i64, usize, usize)> = vec![];
+    for (s, row) in c.iter().enumerate() {
+        for (d, cost) in row.iter().enumerate() {
+            cells.push((*cost, s, d));
+        }
+    }
+    cells.sort_unstable();
+    let mut open = Open::new(rows, cols);
+    for (_, s, d) in cells {
+        if !open.row_out[s] && !open.col_out[d] {
+            open.allocate(&mut basis, s, d);
+        }
+    }
+    basis
+}
+
+// Vogel's approximation: the line with the largest difference between its two cheapest open cells goes first
+fn vogel_bfs(c: &[Vec<i64>], rows: &[u64], cols: &[u64]) -> Basis {
+    let mut basis = Basis::new(rows.len(), cols.len());
+    let mut open = Open::new(rows, cols);
+    while open.rows_left > 0 && open.cols_left > 0 {
+        let mut best: Option<(i64, usize, usize)> = None; // penalty, cell
+        for s in (0..rows.len()).filter(|s| !open.row_out[*s]) {
+            let (penalty, d) = penalty((0..cols.len()).filter(|d| !open.col_out[*d]).map(|d| (c[s][d], d)));
+            if best.is_none_or(|(p, _, _)| penalty > p) {
+                best = Some((penalty, s, d));
+            }
+        }
+        for d in (0..cols.len()).filter(|d| !open.col_out[*d]) {
+            let (penalty, s) = penalty((0..rows.len()).filter(|s| !open.row_out[*s]).map(|s| (c[s][d], s)));
+            if best.is_none_or(|(p, _, _)| penalty > p) {
+                best = Some((penalty, s, d));
+            }
+        }
+        let (_, s, d) = best.unwrap();
+        open.allocate(&mut basis, s, d);
+    }
+    basis
+}
+
+// difference of the two cheapest (the only one counts as the biggest penalty) and where the cheapest is
+fn penalty(cells: impl Iterator<Item = (i64, usize)>) -> (i64, usize) {
+    let mut first: Option<(i64, usize)> = None;
+    let mut second: Option<i64> = None;
+    for (cost, i) in cells {
+        match first {
+            Some((f, _)) if cost >= f => second = Some(second.map_or(cost, |s| cmp::min(s, cost))),
+            _ => {
+                second = first.map(|(f, _)| f);
+                first = Some((cost, i));
+            }
+        }
+    }
+    let (f, i) = first.unwrap();
+    (second.map_or(i64::MAX, |s| s - f), i)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinding::prelude::{kuhn_munkres_min, Matrix};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::forbidden::Forbidden;
+    use crate::{test_cost_arr, test_instance, DSIZE, SSIZE};
+    use super::{transport, validate, InitialBfs};
+
+    // the optimum of the unit-assignment expansion: a row per unit of supply, a column per unit of demand,
+    // free dummies making it square; forbidden pairs cost the big M of 'transport', so the cost is the same
+    // when it cannot do without them
+    fn expanded_cost(cost_arr: &[[u16; DSIZE]; SSIZE], forb: &Forbidden, supply: &[u32], demand: &[u32]) -> u64 {
+        let units = |q: &[u32]| q.iter().enumerate().flat_map(|(i, n)| std::iter::repeat_n(i, *n as usize)).collect::<Vec<usize>>();
+        let (rows, cols) = (units(supply), units(demand));
+        let size = rows.len().max(cols.len());
+        let max_cost = (0..supply.len()).flat_map(|s| (0..demand.len()).map(move |d| (s, d)))
+            .filter(|(s, d)| !forb.is_forbidden(*s, *d)).map(|(s, d)| cost_arr[s][d] as i64).max().unwrap_or(0);
+        let big = max_cost * size as i64 + 1;
+        let weights = Matrix::from_fn(size, size, |(r, c)| match (rows.get(r), cols.get(c)) {
+            (Some(s), Some(d)) if forb.is_forbidden(*s, *d) => big,
+            (Some(s), Some(d)) => cost_arr[*s][*d] as i64,
+            _ => 0,
+        });
+        kuhn_munkres_min(&weights).0 as u64
+    }
+
+    #[test]
+    fn transport_matches_expansion() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(6);
+        for (s_size, d_size) in [(1, 1), (2, 3), (3, 2), (3, 3), (4, 4), (2, 5)] {
+            for _ in 0..10 {
+                let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 30, 0);
+                let supply: Vec<u32> = (0..s_size).map(|_| rng.gen_range(1..=4)).collect();
+                let demand: Vec<u32> = (0..d_size).map(|_| rng.gen_range(1..=4)).collect();
+                let expected = expanded_cost(&cost_arr, &forb, &supply, &demand);
+                for init in [InitialBfs::Lcm, InitialBfs::Vogel] {
+                    let plan = transport(&cost_arr, &forb, &supply, &demand, init);
+                    assert!(validate(&plan, &forb, &supply, &demand), "{:?} {:?} {:?}", supply, demand, init);
+                    assert_eq!(plan.cost, expected, "{:?} {:?} {:?}", supply, demand, init);
+                }
+            }
+        }
+    }
+
+    // a plan needs forbidden pairs only when the allowed ones cannot carry the quantities; it is then invalid,
+    // at the cost of the expansion with forbidden pairs at big M
+    #[test]
+    fn transport_forbidden_pairs() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(14);
+        let mut infeasible = 0;
+        for (s_size, d_size) in [(2, 2), (2, 3), (3, 2), (3, 3), (4, 4), (3, 5)] {
+            for forbidden_pct in [20, 40, 60] {
+                for _ in 0..10 {
+                    let forb = test_instance(&mut cost_arr, &mut rng, s_size, d_size, 30, forbidden_pct);
+                    let supply: Vec<u32> = (0..s_size).map(|_| rng.gen_range(1..=3)).collect();
+                    let demand: Vec<u32> = (0..d_size).map(|_| rng.gen_range(1..=3)).collect();
+                    let expected = expanded_cost(&cost_arr, &forb, &supply, &demand);
+                    let what = format!("{:?} {:?}, {}% forbidden", supply, demand, forbidden_pct);
+                    for init in [InitialBfs::Lcm, InitialBfs::Vogel] {
+                        let plan = transport(&cost_arr, &forb, &supply, &demand, init);
+                        assert_eq!(plan.cost, expected, "{} {:?}", what, init);
+                        let uses_forbidden = plan.flow.iter().enumerate()
+                            .any(|(s, row)| row.iter().enumerate().any(|(d, q)| *q > 0 && forb.is_forbidden(s, d)));
+                        assert_eq!(validate(&plan, &forb, &supply, &demand), !uses_forbidden, "{} {:?}", what, init);
+                        infeasible += uses_forbidden as usize;
+                    }
+                }
+            }
+        }
+        assert!(infeasible > 0, "no instance needed a forbidden pair");
+    }
+
+    // every quantity 1 is an assignment: n - 1 cells of the basis ship 0, most pivots are degenerate and the
+    // anti-cycling rule has to end them
+    #[test]
+    fn transport_degenerate_assignment() {
+        let mut cost_arr = test_cost_arr();
+        let mut rng = StdRng::seed_from_u64(7);
+        for size in [2, 4, 6, 8] {
+            for max in [1, 3, 100] { // few distinct costs make ties
+                let forb = test_instance(&mut cost_arr, &mut rng, size, size, max, 0);
+                let ones = vec![1; size];
+                let expected = expanded_cost(&cost_arr, &forb, &ones, &ones);
+                for init in [InitialBfs::Lcm, InitialBfs::Vogel] {
+                    let plan = transport(&cost_arr, &forb, &ones, &ones, init);
+                    assert!(validate(&plan, &forb, &ones, &ones));
+                    assert_eq!(plan.cost, expected, "{}x{}, costs 0..={}, {:?}", size, size, max, init);
+                }
+            }
+        }
+    }
+}